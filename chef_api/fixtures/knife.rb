@@ -0,0 +1,13 @@
+# See https://docs.chef.io/workstation/config_rb/ for more information on knife configuration options
+
+current_dir = File.dirname(__FILE__)
+log_level                :info
+log_location             STDOUT
+node_name                "barney"
+client_key               "#{current_dir}/barney.pem"
+chef_server_url          "https://api.chef.io/organizations/bedrock"
+cookbook_path            ["#{current_dir}/../cookbooks"]
+ssl_verify_mode          :verify_none
+trusted_certs_dir        "#{current_dir}/trusted_certs"
+https_proxy              'http://proxy.bedrock.example:3128'
+no_proxy                 'localhost,127.0.0.1'
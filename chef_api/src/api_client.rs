@@ -1,12 +1,18 @@
-use crate::credentials::Config;
+use crate::authentication::signer::{PemSigner, Signer};
+use crate::credentials::{Config, SslVerifyMode};
+use crate::doctor::{request_diagnostic, Check, Diagnostic};
+use crate::errors::ChefError;
 
 use hyper::client::HttpConnector;
 use hyper::Client as HyperClient;
 use hyper_tls::HttpsConnector;
+use native_tls::{Certificate, TlsConnector};
 
+use chrono::Duration;
 use failure::Error;
 use serde::ser::*;
 use serde_json::Value;
+use std::cell::Cell;
 use std::rc::Rc;
 
 use crate::requests::*;
@@ -18,21 +24,70 @@ pub struct ApiClient {
     pub config: Config,
     /// The Hyper HTTP Client.
     pub client: Rc<HyperClient<HttpsConnector<HttpConnector>>>,
+    /// Signs requests; if not set, requests are signed with the key from `config`.
+    pub signer: Option<Rc<dyn Signer>>,
+    /// Seconds to add to the local clock when signing requests, learnt from the `Date` header
+    /// of the Chef Server's responses.
+    pub(crate) clock_offset: Rc<Cell<i64>>,
 }
 
 impl ApiClient {
     /// Create a new ApiClient struct. It takes a `Config` type. Typically one would use
     /// `from_credentials` rather than calling this directly.
+    ///
+    /// The connection to the Chef Server honours the config's `ssl_verify_mode` and
+    /// `trusted_certs_dir`.
     pub fn new(config: Config) -> Result<Self, Error> {
-        let https = HttpsConnector::new();
+        let https = https_connector(&config)?;
         let client = HyperClient::builder().build(https);
 
         Ok(Self {
             config,
             client: Rc::new(client),
+            signer: None,
+            clock_offset: Rc::new(Cell::new(0)),
         })
     }
 
+    /// Sign requests with the configured client key, calling `passphrase` to get its passphrase
+    /// if it is encrypted and neither the environment nor the profile provide one.
+    ///
+    /// ```rust,no_run
+    /// # use chef_api::api_client::ApiClient;
+    /// let client = ApiClient::from_credentials(None)
+    ///     .and_then(|c| c.with_passphrase(|| Ok(String::from("correct horse"))))
+    ///     .unwrap();
+    /// ```
+    pub fn with_passphrase<F>(self, passphrase: F) -> Result<Self, Error>
+    where
+        F: FnOnce() -> Result<String, Error>,
+    {
+        let key = self.config.private_key_with(passphrase)?;
+        Ok(self.with_signer(PemSigner::new(key)))
+    }
+
+    /// Returns the signer requests are made with: the one given to `with_signer` or
+    /// `with_passphrase`, or otherwise one using the configured client key.
+    pub fn request_signer(&self) -> Result<Rc<dyn Signer>, Error> {
+        match self.signer {
+            Some(ref signer) => Ok(signer.clone()),
+            None => Ok(Rc::new(PemSigner::new(self.config.private_key()?))),
+        }
+    }
+
+    /// How far the Chef Server's clock was found to be ahead of the local clock; requests are
+    /// signed with this offset applied.
+    pub fn clock_offset(&self) -> Duration {
+        Duration::seconds(self.clock_offset.get())
+    }
+
+    /// Sign requests using `signer` rather than the key in the client's configuration, for
+    /// example to use a key held in an HSM or an agent.
+    pub fn with_signer<S: Signer + 'static>(mut self, signer: S) -> Self {
+        self.signer = Some(Rc::new(signer));
+        self
+    }
+
     /// Create a new `ApiClient` struct using a set of credentials as defined in Chef RFC 99.
     ///
     /// Accepts an `Option<String>` containing the name of a credentials profile.
@@ -40,6 +95,59 @@ impl ApiClient {
         Config::from_credentials(profile).map(ApiClient::new)?
     }
 
+    /// Create a new `ApiClient` struct using only environment variables, as described in
+    /// `Config::from_env`.
+    pub fn from_env() -> Result<Self, Error> {
+        Config::from_env().map(ApiClient::new)?
+    }
+
+    /// Checks the client's configuration, as `Config::validate` does, then makes a signed test
+    /// request to the Chef Server. Returns an empty list if all is well.
+    ///
+    /// ```rust,no_run
+    /// # use chef_api::api_client::ApiClient;
+    /// let client = ApiClient::from_credentials(None).unwrap();
+    /// for diagnostic in client.validate() {
+    ///     println!("{}", diagnostic);
+    /// }
+    /// ```
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.config.validate();
+        if self.signer.is_some() {
+            // The configured key isn't used to sign requests.
+            diagnostics.retain(|d| d.check != Check::ClientKey);
+        }
+        if !diagnostics.is_empty() {
+            return diagnostics;
+        }
+
+        // Any member of an organization may read its _default environment, so a 403 means the
+        // client isn't one; any user may read their own record.
+        let name = self.config.client_name().unwrap_or_default();
+        let organization = self.config.organization_path().unwrap_or_default();
+        let (path, result) = if organization.starts_with("/organizations/") {
+            let mut request = self.environments();
+            request.environment("_default");
+            (
+                request.path.clone(),
+                request.execute_raw(None::<String>, "get"),
+            )
+        } else {
+            let mut request = self.server_users();
+            request.user(name);
+            (
+                request.path.clone(),
+                request.execute_raw(None::<String>, "get"),
+            )
+        };
+        diagnostics.extend(request_diagnostic(
+            &self.config,
+            &path,
+            result.map(|(status, _)| status),
+        ));
+        diagnostics
+    }
+
     build!(clients, ClientsQuery);
     build!(containers, ContainersQuery);
     build!(controls, ControlsQuery);
@@ -101,6 +209,62 @@ pub trait Execute {
 
     #[doc(hidden)]
     fn execute<B>(&self, body: Option<B>, method: &str) -> Result<Value, Error>
+    where
+        B: Serialize,
+    {
+        let (status, body) = self.execute_raw(body, method)?;
+        if (200..300).contains(&status) {
+            Ok(body)
+        } else {
+            Err(ChefError::ChefServerResponseError(status).into())
+        }
+    }
+
+    /// Make an HTTP request, returning the status code and body of the response, even if the Chef
+    /// Server reported an error.
+    #[doc(hidden)]
+    fn execute_raw<B>(&self, body: Option<B>, method: &str) -> Result<(u16, Value), Error>
     where
         B: Serialize;
 }
+/// Builds the connector for `config`, trusting the certificates in its `trusted_certs_dir` and
+/// skipping verification if its `ssl_verify_mode` is `verify_none`.
+fn https_connector(config: &Config) -> Result<HttpsConnector<HttpConnector>, Error> {
+    let mut tls = TlsConnector::builder();
+    if config.ssl_verify_mode == Some(SslVerifyMode::VerifyNone) {
+        warn!(
+            "Not verifying the TLS certificate of {}, as ssl_verify_mode is verify_none",
+            config.chef_server_url
+        );
+        tls.danger_accept_invalid_certs(true);
+    }
+    for cert in config.trusted_certs()? {
+        tls.add_root_certificate(Certificate::from_der(&cert.to_der()?)?);
+    }
+    if config.http_proxy.is_some() || config.https_proxy.is_some() {
+        warn!(
+            "Proxies are not supported; connecting to {} directly",
+            config.chef_server_url
+        );
+    }
+
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    Ok(HttpsConnector::from((http, tls.build()?.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_https_connector() {
+        let mut config = Config::from_knife_config(Some("fixtures/knife.rb")).unwrap();
+        assert_eq!(config.trusted_certs().unwrap().len(), 1);
+        assert!(https_connector(&config).is_ok());
+
+        config.ssl_verify_mode = None;
+        config.trusted_certs_dir = None;
+        assert!(https_connector(&config).is_ok());
+    }
+}
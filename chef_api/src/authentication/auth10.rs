@@ -0,0 +1,166 @@
+use crate::authentication::auth11::{content_hash, sha1_base64};
+use crate::authentication::signer::{SignatureScheme, Signer};
+use crate::utils::{expand_string, squeeze_path};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::*;
+use failure::Error;
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+use itertools::Itertools;
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+
+/// Protocol version 1.0, which is 1.1 without hashing the user id. Still required by some
+/// older Chef Servers and Supermarket.
+pub struct Auth10 {
+    body: Option<String>,
+    date: String,
+    signer: Rc<dyn Signer>,
+    method: String,
+    path: String,
+    userid: String,
+}
+
+impl fmt::Debug for Auth10 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Auth10")
+            .field("method", &self.method)
+            .field("userid", &self.userid)
+            .field("path", &self.path)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+impl Auth10 {
+    pub fn new(
+        path: &str,
+        signer: Rc<dyn Signer>,
+        method: &str,
+        userid: &str,
+        body: Option<String>,
+    ) -> Auth10 {
+        let dt = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+        let userid: String = userid.into();
+        let method = String::from(method).to_ascii_uppercase();
+
+        Auth10 {
+            body,
+            date: dt,
+            signer,
+            method,
+            path: squeeze_path(path),
+            userid,
+        }
+    }
+
+    /// Stamp the request with `time` rather than the current time.
+    pub fn timestamp(mut self, time: DateTime<Utc>) -> Self {
+        self.date = time.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        self
+    }
+
+    fn content_hash(&self) -> Result<String, Error> {
+        content_hash(&expand_string(&self.body))
+    }
+
+    fn canonical_request(&self) -> Result<String, Error> {
+        canonical_request(
+            &self.method,
+            &self.path,
+            &self.content_hash()?,
+            &self.date,
+            &self.userid,
+        )
+    }
+
+    fn encrypted_request(&self) -> Result<String, Error> {
+        let cr = self.canonical_request()?;
+        let signature = self.signer.sign(SignatureScheme::RsaRaw, cr.as_bytes())?;
+        Ok(general_purpose::STANDARD.encode(signature))
+    }
+
+    pub fn build(self, headers: &mut HeaderMap) -> Result<(), Error> {
+        let hsh = self.content_hash()?;
+        headers.insert("X-Ops-Content-Hash", HeaderValue::from_str(&hsh)?);
+
+        headers.insert(
+            "X-Ops-Sign",
+            HeaderValue::from_str("algorithm=sha1;version=1.0")?,
+        );
+        headers.insert("X-Ops-Timestamp", HeaderValue::from_str(&self.date)?);
+        headers.insert("X-Ops-Userid", HeaderValue::from_str(&self.userid)?);
+
+        let enc = self.encrypted_request()?;
+        for (i, h) in (1..).zip(&enc.bytes().chunks(60)) {
+            let key = format!("X-Ops-Authorization-{}", i);
+            headers.insert(
+                HeaderName::try_from(key)?,
+                HeaderValue::from_bytes(&h.collect::<Vec<_>>())?,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The string signed by protocol version 1.0; only the path is hashed.
+pub(crate) fn canonical_request(
+    method: &str,
+    path: &str,
+    content_hash: &str,
+    date: &str,
+    userid: &str,
+) -> Result<String, Error> {
+    let cr = format!(
+        "Method:{}\nHashed Path:{}\n\
+         X-Ops-Content-Hash:{}\n\
+         X-Ops-Timestamp:{}\nX-Ops-UserId:{}",
+        method,
+        sha1_base64(path)?,
+        content_hash,
+        date,
+        userid
+    );
+    debug!("Canonical Request is: {:?}", cr);
+    Ok(cr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Auth10;
+    use crate::authentication::signer::PemSigner;
+    use std::fs::File;
+    use std::io::Read;
+    use std::rc::Rc;
+
+    const PATH: &str = "/organizations/clownco";
+    const BODY: &str = "Spec Body";
+    const USER: &str = "spec-user";
+    const DT: &str = "2009-01-01T12:00:00Z";
+
+    const PRIVATE_KEY: &str = "fixtures/spec-user.pem";
+
+    #[test]
+    fn test_canonical_request() {
+        let mut key = String::new();
+        File::open(PRIVATE_KEY)
+            .and_then(|mut fh| fh.read_to_string(&mut key))
+            .unwrap();
+        let auth = Auth10 {
+            body: Some(String::from(BODY)),
+            date: String::from(DT),
+            signer: Rc::new(PemSigner::from_pem(key.as_bytes()).unwrap()),
+            method: String::from("POST"),
+            path: String::from(PATH),
+            userid: String::from(USER),
+        };
+        assert_eq!(
+            auth.canonical_request().unwrap(),
+            "Method:POST\nHashed \
+             Path:YtBWDn1blGGuFIuKksdwXzHU9oE=\nX-Ops-Content-Hash:\
+             DFteJZPVv6WKdQmMqZUQUumUyRs=\nX-Ops-Timestamp:2009-01-01T12:00:\
+             00Z\nX-Ops-UserId:spec-user"
+        )
+    }
+}
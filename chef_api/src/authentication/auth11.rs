@@ -1,3 +1,4 @@
+use crate::authentication::signer::{SignatureScheme, Signer};
 use crate::utils::{expand_string, squeeze_path};
 use base64::{engine::general_purpose, Engine as _};
 use chrono::*;
@@ -5,17 +6,16 @@ use failure::Error;
 use hyper::header::{HeaderMap, HeaderName, HeaderValue};
 use itertools::Itertools;
 use openssl::hash::{hash, MessageDigest};
-use openssl::rsa::Padding;
-use openssl::rsa::Rsa;
 use std::convert::TryFrom;
 use std::fmt;
+use std::rc::Rc;
 
 pub struct Auth11 {
     #[allow(dead_code)]
     api_version: String,
     body: Option<String>,
     date: String,
-    key: Vec<u8>,
+    signer: Rc<dyn Signer>,
     method: String,
     path: String,
     userid: String,
@@ -35,7 +35,7 @@ impl fmt::Debug for Auth11 {
 impl Auth11 {
     pub fn new(
         path: &str,
-        key: &[u8],
+        signer: Rc<dyn Signer>,
         method: &str,
         userid: &str,
         api_version: &str,
@@ -50,58 +50,42 @@ impl Auth11 {
             api_version: api_version.into(),
             body,
             date: dt,
-            key: key.into(),
+            signer,
             method,
             path: squeeze_path(path),
             userid,
         }
     }
 
-    fn hashed_path(&self) -> Result<String, Error> {
-        debug!("Path is: {:?}", self.path);
-        let hash = hash(MessageDigest::sha1(), self.path.as_bytes())?;
-        let hash = general_purpose::STANDARD.encode(hash);
-        Ok(hash)
+    /// Stamp the request with `time` rather than the current time.
+    pub fn timestamp(mut self, time: DateTime<Utc>) -> Self {
+        self.date = time.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        self
     }
 
     fn content_hash(&self) -> Result<String, Error> {
-        let body = expand_string(&self.body);
-        let content = hash(MessageDigest::sha1(), body.as_bytes())?;
-        let content = general_purpose::STANDARD.encode(content);
-        debug!("{:?}", content);
-        Ok(content)
+        content_hash(&expand_string(&self.body))
     }
 
+    #[cfg(test)]
     fn canonical_user_id(&self) -> Result<String, Error> {
-        hash(MessageDigest::sha1(), self.userid.as_bytes())
-            .and_then(|res| Ok(general_purpose::STANDARD.encode(res)))
-            .map_err(|res| res.into())
+        sha1_base64(&self.userid)
     }
 
     fn canonical_request(&self) -> Result<String, Error> {
-        let cr = format!(
-            "Method:{}\nHashed Path:{}\n\
-             X-Ops-Content-Hash:{}\n\
-             X-Ops-Timestamp:{}\nX-Ops-UserId:{}",
+        canonical_request(
             &self.method,
-            self.hashed_path()?,
-            self.content_hash()?,
-            self.date,
-            self.canonical_user_id()?
-        );
-        debug!("Canonical Request is: {:?}", cr);
-        Ok(cr)
+            &self.path,
+            &self.content_hash()?,
+            &self.date,
+            &self.userid,
+        )
     }
 
     fn encrypted_request(&self) -> Result<String, Error> {
-        let key = Rsa::private_key_from_pem(self.key.as_slice())?;
-
         let cr = self.canonical_request()?;
-        let cr = cr.as_bytes();
-
-        let mut hash: Vec<u8> = vec![0; key.size() as usize];
-        key.private_encrypt(cr, &mut hash, Padding::PKCS1)?;
-        Ok(general_purpose::STANDARD.encode(hash))
+        let signature = self.signer.sign(SignatureScheme::RsaRaw, cr.as_bytes())?;
+        Ok(general_purpose::STANDARD.encode(signature))
     }
 
     pub fn build(self, headers: &mut HeaderMap) -> Result<(), Error> {
@@ -116,24 +100,59 @@ impl Auth11 {
         headers.insert("X-Ops-Userid", HeaderValue::from_str(&self.userid)?);
 
         let enc = self.encrypted_request()?;
-        let mut i = 1;
-        for h in &enc.bytes().chunks(60) {
+        for (i, h) in (1..).zip(&enc.bytes().chunks(60)) {
             let key = format!("X-Ops-Authorization-{}", i);
             headers.insert(
                 HeaderName::try_from(key)?,
                 HeaderValue::from_bytes(&h.collect::<Vec<_>>())?,
             );
-            i += 1;
         }
         Ok(())
     }
 }
 
+pub(crate) fn sha1_base64(data: &str) -> Result<String, Error> {
+    let hash = hash(MessageDigest::sha1(), data.as_bytes())?;
+    Ok(general_purpose::STANDARD.encode(hash))
+}
+
+/// The base64 encoded SHA-1 hash of a request body.
+pub(crate) fn content_hash(body: &str) -> Result<String, Error> {
+    let content = sha1_base64(body)?;
+    debug!("{:?}", content);
+    Ok(content)
+}
+
+/// The string signed by protocol version 1.1; the path and user id are hashed.
+pub(crate) fn canonical_request(
+    method: &str,
+    path: &str,
+    content_hash: &str,
+    date: &str,
+    userid: &str,
+) -> Result<String, Error> {
+    debug!("Path is: {:?}", path);
+    let cr = format!(
+        "Method:{}\nHashed Path:{}\n\
+         X-Ops-Content-Hash:{}\n\
+         X-Ops-Timestamp:{}\nX-Ops-UserId:{}",
+        method,
+        sha1_base64(path)?,
+        content_hash,
+        date,
+        sha1_base64(userid)?
+    );
+    debug!("Canonical Request is: {:?}", cr);
+    Ok(cr)
+}
+
 #[cfg(test)]
 mod tests {
     use super::Auth11;
+    use crate::authentication::signer::{PemSigner, Signer};
     use std::fs::File;
     use std::io::Read;
+    use std::rc::Rc;
 
     const PATH: &str = "/organizations/clownco";
     const BODY: &str = "Spec Body";
@@ -142,12 +161,12 @@ mod tests {
 
     const PRIVATE_KEY: &str = "fixtures/spec-user.pem";
 
-    fn get_key_data() -> Vec<u8> {
+    fn get_signer() -> Rc<dyn Signer> {
         let mut key = String::new();
         File::open(PRIVATE_KEY)
             .and_then(|mut fh| fh.read_to_string(&mut key))
             .unwrap();
-        key.into_bytes()
+        Rc::new(PemSigner::from_pem(key.as_bytes()).unwrap())
     }
 
     #[test]
@@ -156,7 +175,7 @@ mod tests {
             api_version: String::from("1"),
             body: Some(String::from(BODY)),
             date: String::from(DT),
-            key: get_key_data(),
+            signer: get_signer(),
             method: String::from("POST"),
             path: String::from(PATH),
             userid: String::from(USER),
@@ -173,7 +192,7 @@ mod tests {
             api_version: String::from("1"),
             body: Some(String::from(BODY)),
             date: String::from(DT),
-            key: get_key_data(),
+            signer: get_signer(),
             method: String::from("POST"),
             path: String::from(PATH),
             userid: String::from(USER),
@@ -193,7 +212,7 @@ mod tests {
             api_version: String::from("1"),
             body: Some(String::from(BODY)),
             date: String::from(DT),
-            key: get_key_data(),
+            signer: get_signer(),
             method: String::from("POST"),
             path: String::from(PATH),
             userid: String::from(USER),
@@ -1,3 +1,5 @@
+use crate::authentication::signer::{SignatureScheme, Signer};
+use crate::authentication::SignAlgorithm;
 use crate::utils::{expand_string, squeeze_path};
 use base64::{engine::general_purpose, Engine as _};
 use chrono::*;
@@ -5,16 +7,16 @@ use failure::Error;
 use hyper::header::{HeaderMap, HeaderName, HeaderValue};
 use itertools::Itertools;
 use openssl::hash::{hash, MessageDigest};
-use openssl::pkey::PKey;
-use openssl::sign::Signer;
 use std::convert::TryFrom;
 use std::fmt;
+use std::rc::Rc;
 
 pub struct Auth13 {
+    algorithm: SignAlgorithm,
     api_version: String,
     body: Option<String>,
     date: String,
-    key: Vec<u8>,
+    signer: Rc<dyn Signer>,
     method: String,
     path: String,
     userid: String,
@@ -23,6 +25,7 @@ pub struct Auth13 {
 impl fmt::Debug for Auth13 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Auth13")
+            .field("algorithm", &self.algorithm)
             .field("method", &self.method)
             .field("userid", &self.userid)
             .field("path", &self.path)
@@ -34,7 +37,7 @@ impl fmt::Debug for Auth13 {
 impl Auth13 {
     pub fn new(
         path: &str,
-        key: &[u8],
+        signer: Rc<dyn Signer>,
         method: &str,
         userid: &str,
         api_version: &str,
@@ -46,50 +49,53 @@ impl Auth13 {
         let method = String::from(method).to_ascii_uppercase();
 
         Auth13 {
+            algorithm: SignAlgorithm::default(),
             api_version: api_version.into(),
             body,
             date: dt,
-            key: key.into(),
+            signer,
             method,
             path: squeeze_path(path),
             userid,
         }
     }
 
+    /// Stamp the request with `time` rather than the current time.
+    pub fn timestamp(mut self, time: DateTime<Utc>) -> Self {
+        self.date = time.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        self
+    }
+
+    /// Use `algorithm` for hashing and signing rather than the default of SHA-256.
+    pub fn algorithm(mut self, algorithm: SignAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
     fn content_hash(&self) -> Result<String, Error> {
         let body = expand_string(&self.body);
         debug!("Content body is: {:?}", body);
-        let content = hash(MessageDigest::sha256(), body.as_bytes())?;
-        let content = general_purpose::STANDARD.encode(content);
-        debug!("Content hash is: {:?}", content);
-        Ok(content)
+        content_hash(&body, self.algorithm)
     }
 
     fn canonical_request(&self) -> Result<String, Error> {
-        let cr = format!(
-            "Method:{}\nPath:{}\nX-Ops-Content-Hash:{}\n\
-             X-Ops-Sign:version=1.3\nX-Ops-Timestamp:{}\n\
-             X-Ops-UserId:{}\nX-Ops-Server-API-Version:{}",
+        Ok(canonical_request(
             &self.method,
             &self.path,
-            self.content_hash()?,
-            self.date,
+            &self.content_hash()?,
+            &self.date,
             &self.userid,
-            &self.api_version
-        );
-        debug!("Canonical Request is: {:?}", cr);
-        Ok(cr)
+            &self.api_version,
+        ))
     }
 
     fn signed_request(&self) -> Result<String, Error> {
-        let key = PKey::private_key_from_pem(self.key.as_slice())?;
-
         let cr = self.canonical_request()?;
-        let cr = cr.as_bytes();
-
-        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
-        signer.update(cr).unwrap();
-        let result = signer.sign_to_vec()?;
+        let scheme = match self.algorithm {
+            SignAlgorithm::Sha1 => SignatureScheme::RsaSha1,
+            SignAlgorithm::Sha256 => SignatureScheme::RsaSha256,
+        };
+        let result = self.signer.sign(scheme, cr.as_bytes())?;
         let result = general_purpose::STANDARD.encode(result);
         debug!("base64 encoded result is {:?}", result);
         Ok(result)
@@ -101,27 +107,59 @@ impl Auth13 {
         headers.insert("X-Ops-Content-Hash", HeaderValue::from_str(&hsh)?);
         headers.insert(
             "X-Ops-Sign",
-            HeaderValue::from_str("algorithm=sha256;version=1.3")?,
+            HeaderValue::from_str(&format!("algorithm={};version=1.3", self.algorithm))?,
         );
         headers.insert("X-Ops-Timestamp", HeaderValue::from_str(&self.date)?);
         headers.insert("X-Ops-Userid", HeaderValue::from_str(&self.userid)?);
 
         let enc = self.signed_request()?;
-        let mut i = 1;
-        for h in &enc.bytes().chunks(60) {
+        for (i, h) in (1..).zip(&enc.bytes().chunks(60)) {
             let key = format!("X-Ops-Authorization-{}", i);
             let value = h.collect::<Vec<_>>();
             let value = HeaderValue::from_bytes(&value)?;
             headers.insert(HeaderName::try_from(key)?, value);
-            i += 1;
         }
         Ok(())
     }
 }
 
+/// The base64 encoded hash of a request body.
+pub(crate) fn content_hash(body: &str, algorithm: SignAlgorithm) -> Result<String, Error> {
+    let digest = match algorithm {
+        SignAlgorithm::Sha1 => MessageDigest::sha1(),
+        SignAlgorithm::Sha256 => MessageDigest::sha256(),
+    };
+    let content = hash(digest, body.as_bytes())?;
+    let content = general_purpose::STANDARD.encode(content);
+    debug!("Content hash is: {:?}", content);
+    Ok(content)
+}
+
+/// The string signed by protocol version 1.3.
+pub(crate) fn canonical_request(
+    method: &str,
+    path: &str,
+    content_hash: &str,
+    date: &str,
+    userid: &str,
+    api_version: &str,
+) -> String {
+    let cr = format!(
+        "Method:{}\nPath:{}\nX-Ops-Content-Hash:{}\n\
+         X-Ops-Sign:version=1.3\nX-Ops-Timestamp:{}\n\
+         X-Ops-UserId:{}\nX-Ops-Server-API-Version:{}",
+        method, path, content_hash, date, userid, api_version
+    );
+    debug!("Canonical Request is: {:?}", cr);
+    cr
+}
+
 #[cfg(test)]
 mod tests {
     use super::Auth13;
+    use crate::authentication::signer::{PemSigner, Signer};
+    use crate::authentication::SignAlgorithm;
+    use std::rc::Rc;
 
     use base64::{engine::general_purpose, Engine as _};
     use openssl::hash::MessageDigest;
@@ -137,21 +175,22 @@ mod tests {
 
     const PRIVATE_KEY: &str = "fixtures/spec-user.pem";
 
-    fn get_key_data() -> Vec<u8> {
+    fn get_signer() -> Rc<dyn Signer> {
         let mut key = String::new();
         File::open(PRIVATE_KEY)
             .and_then(|mut fh| fh.read_to_string(&mut key))
             .unwrap();
-        key.into_bytes()
+        Rc::new(PemSigner::from_pem(key.as_bytes()).unwrap())
     }
 
     #[test]
     fn test_canonical_request() {
         let auth = Auth13 {
+            algorithm: SignAlgorithm::Sha256,
             api_version: String::from("1"),
             body: Some(String::from(BODY)),
             date: String::from(DT),
-            key: get_key_data(),
+            signer: get_signer(),
             method: String::from("POST"),
             path: String::from(PATH),
             userid: String::from(USER),
@@ -168,10 +207,11 @@ mod tests {
     #[test]
     fn test_signed_request() {
         let auth = Auth13 {
+            algorithm: SignAlgorithm::Sha256,
             api_version: String::from("1"),
             body: Some(String::from(BODY)),
             date: String::from(DT),
-            key: get_key_data(),
+            signer: get_signer(),
             method: String::from("POST"),
             path: String::from(PATH),
             userid: String::from(USER),
@@ -179,7 +219,7 @@ mod tests {
         let sig = &auth.signed_request().unwrap();
         let req = &auth.canonical_request().unwrap();
 
-        let sig_raw = general_purpose::STANDARD.decode(&sig).unwrap();
+        let sig_raw = general_purpose::STANDARD.decode(sig).unwrap();
         let mut key: Vec<u8> = vec![];
         let mut fh = File::open(PRIVATE_KEY).unwrap();
         fh.read_to_end(&mut key).unwrap();
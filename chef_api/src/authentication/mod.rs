@@ -1,2 +1,102 @@
+use crate::errors::ChefError;
+use failure::Error;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+pub mod auth10;
 pub mod auth11;
 pub mod auth13;
+pub mod signer;
+pub mod verifier;
+
+/// The version of the Chef authentication protocol used to sign requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SignVersion {
+    V1_0,
+    V1_1,
+    #[default]
+    V1_3,
+}
+
+impl SignVersion {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SignVersion::V1_0 => "1.0",
+            SignVersion::V1_1 => "1.1",
+            SignVersion::V1_3 => "1.3",
+        }
+    }
+}
+
+impl FromStr for SignVersion {
+    type Err = Error;
+
+    fn from_str(version: &str) -> Result<Self, Error> {
+        match version.trim() {
+            "1.0" => Ok(SignVersion::V1_0),
+            "1.1" => Ok(SignVersion::V1_1),
+            "1.3" => Ok(SignVersion::V1_3),
+            _ => Err(ChefError::InvalidSignVersionError(version.into()).into()),
+        }
+    }
+}
+
+impl TryFrom<String> for SignVersion {
+    type Error = Error;
+
+    fn try_from(version: String) -> Result<Self, Error> {
+        version.parse()
+    }
+}
+
+impl fmt::Display for SignVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The digest used by protocol version 1.3. Versions 1.0 and 1.1 always use SHA-1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SignAlgorithm {
+    Sha1,
+    #[default]
+    Sha256,
+}
+
+impl SignAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SignAlgorithm::Sha1 => "sha1",
+            SignAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+impl FromStr for SignAlgorithm {
+    type Err = Error;
+
+    fn from_str(algorithm: &str) -> Result<Self, Error> {
+        match algorithm.trim() {
+            "sha1" => Ok(SignAlgorithm::Sha1),
+            "sha256" => Ok(SignAlgorithm::Sha256),
+            _ => Err(ChefError::InvalidSignAlgorithmError(algorithm.into()).into()),
+        }
+    }
+}
+
+impl TryFrom<String> for SignAlgorithm {
+    type Error = Error;
+
+    fn try_from(algorithm: String) -> Result<Self, Error> {
+        algorithm.parse()
+    }
+}
+
+impl fmt::Display for SignAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,125 @@
+//! Request signing.
+//!
+//! Every signed request is ultimately an RSA operation with the client's private key. The
+//! `Signer` trait abstracts that operation, so the key may live outside of the process: in an
+//! HSM through PKCS#11, in a cloud KMS, or behind a local agent socket. `PemSigner` is the
+//! built-in implementation, used when a client is configured with a PEM key.
+//!
+//! ```rust,no_run
+//! use chef_api::api_client::ApiClient;
+//! use chef_api::authentication::signer::{SignatureScheme, Signer};
+//! use failure::Error;
+//!
+//! #[derive(Debug)]
+//! struct AgentSigner {
+//!     socket: String,
+//! }
+//!
+//! impl Signer for AgentSigner {
+//!     fn sign(&self, scheme: SignatureScheme, data: &[u8]) -> Result<Vec<u8>, Error> {
+//!         // Ask the agent listening on `self.socket` to sign `data`.
+//!         Err(failure::format_err!("no agent is listening on {}", self.socket))
+//!     }
+//! }
+//!
+//! let signer = AgentSigner { socket: String::from("/run/chef-agent.sock") };
+//! let client = ApiClient::from_credentials(None).unwrap().with_signer(signer);
+//! ```
+
+use crate::errors::ChefError;
+use failure::Error;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Padding;
+use openssl::sign;
+use std::fmt;
+
+/// The RSA operations used by the Chef authentication protocols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+    /// An RSASSA-PKCS1-v1_5 signature over the SHA-256 digest of the data, as used by protocol
+    /// version 1.3.
+    RsaSha256,
+    /// An RSASSA-PKCS1-v1_5 signature over the SHA-1 digest of the data, as used by protocol
+    /// version 1.3 with `algorithm=sha1`.
+    RsaSha1,
+    /// The raw RSA private key operation on the data, with PKCS#1 v1.5 type 1 padding and no
+    /// digest, as used by protocol versions 1.0 and 1.1. PKCS#11 calls this `CKM_RSA_PKCS`.
+    RsaRaw,
+}
+
+/// Something that can sign requests on behalf of a client.
+pub trait Signer: fmt::Debug {
+    /// Signs `data` using the given scheme, returning the raw signature bytes.
+    fn sign(&self, scheme: SignatureScheme, data: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// Decrypts `data` that was encrypted with the client's public key using PKCS#1 v1.5
+    /// padding, as the secrets of chef-vault items are. Signers that can't decrypt return an
+    /// error, which is the default.
+    fn decrypt(&self, _data: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(ChefError::DecryptionError(String::from(
+            "the configured signer does not support decryption",
+        ))
+        .into())
+    }
+}
+
+/// Signs requests with an RSA private key held in memory.
+#[derive(Clone)]
+pub struct PemSigner {
+    key: PKey<Private>,
+}
+
+impl fmt::Debug for PemSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PemSigner")
+            .field("bits", &self.key.bits())
+            .finish()
+    }
+}
+
+impl PemSigner {
+    /// Creates a new signer from a PEM encoded private key.
+    pub fn from_pem(pem: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            key: PKey::private_key_from_pem(pem)?,
+        })
+    }
+
+    /// Creates a new signer from an existing private key.
+    pub fn new(key: PKey<Private>) -> Self {
+        Self { key }
+    }
+}
+
+impl Signer for PemSigner {
+    fn sign(&self, scheme: SignatureScheme, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match scheme {
+            SignatureScheme::RsaSha256 | SignatureScheme::RsaSha1 => {
+                let digest = if scheme == SignatureScheme::RsaSha1 {
+                    MessageDigest::sha1()
+                } else {
+                    MessageDigest::sha256()
+                };
+                let mut signer = sign::Signer::new(digest, &self.key)?;
+                signer.update(data)?;
+                Ok(signer.sign_to_vec()?)
+            }
+            SignatureScheme::RsaRaw => {
+                let key = self.key.rsa()?;
+                let mut signature: Vec<u8> = vec![0; key.size() as usize];
+                let len = key.private_encrypt(data, &mut signature, Padding::PKCS1)?;
+                signature.truncate(len);
+                Ok(signature)
+            }
+        }
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let key = self.key.rsa()?;
+        let mut decrypted: Vec<u8> = vec![0; key.size() as usize];
+        let len = key.private_decrypt(data, &mut decrypted, Padding::PKCS1)?;
+        decrypted.truncate(len);
+        Ok(decrypted)
+    }
+}
@@ -0,0 +1,367 @@
+//! Verification of signed requests, for services that speak the Chef Server's authentication
+//! protocol.
+//!
+//! ```rust,no_run
+//! use chef_api::authentication::verifier::RequestVerifier;
+//! # use hyper::header::HeaderMap;
+//! # use openssl::pkey::PKey;
+//! # use std::collections::HashMap;
+//! # let (headers, body) = (HeaderMap::new(), String::new());
+//! # let keys: HashMap<String, Vec<u8>> = HashMap::new();
+//!
+//! let request = RequestVerifier::new()
+//!     .verify("POST", "/organizations/bedrock/nodes", &headers, &body, |userid| {
+//!         let pem = keys.get(userid).ok_or_else(|| failure::err_msg("unknown user"))?;
+//!         Ok(PKey::public_key_from_pem(pem)?)
+//!     })
+//!     .unwrap();
+//! println!("Authenticated {}", request.userid);
+//! ```
+
+use crate::authentication::{auth10, auth11, auth13, SignAlgorithm, SignVersion};
+use crate::errors::ChefError;
+use crate::utils::{squeeze_path, MAX_CLOCK_SKEW};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use failure::Error;
+use hyper::header::HeaderMap;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Public};
+use openssl::rsa::Padding;
+use openssl::sign::Verifier;
+
+/// Details of a request that passed verification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedRequest {
+    /// The client or user that signed the request.
+    pub userid: String,
+    /// The protocol version used to sign the request.
+    pub version: SignVersion,
+    /// The digest used to sign the request.
+    pub algorithm: SignAlgorithm,
+    /// The time the request was signed.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Verifies the signature of requests made with protocol version 1.0, 1.1 or 1.3.
+#[derive(Debug, Clone)]
+pub struct RequestVerifier {
+    max_skew: Duration,
+}
+
+impl Default for RequestVerifier {
+    fn default() -> Self {
+        Self {
+            max_skew: Duration::seconds(MAX_CLOCK_SKEW),
+        }
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    ChefError::RequestVerificationError(reason.into()).into()
+}
+
+fn header<'h>(headers: &'h HeaderMap, name: &str) -> Result<&'h str, Error> {
+    headers
+        .get(name)
+        .ok_or_else(|| invalid(&format!("missing {} header", name)))?
+        .to_str()
+        .map_err(|_| invalid(&format!("unreadable {} header", name)))
+}
+
+/// Joins the `X-Ops-Authorization-N` headers, in order, into the base64 signature.
+fn authorization(headers: &HeaderMap) -> Result<String, Error> {
+    let mut signature = String::new();
+    for i in 1.. {
+        match headers.get(format!("X-Ops-Authorization-{}", i).as_str()) {
+            Some(value) => signature.push_str(
+                value
+                    .to_str()
+                    .map_err(|_| invalid("unreadable X-Ops-Authorization header"))?,
+            ),
+            None => break,
+        }
+    }
+    if signature.is_empty() {
+        Err(invalid("missing X-Ops-Authorization headers"))
+    } else {
+        Ok(signature)
+    }
+}
+
+/// Parses `X-Ops-Sign`, e.g. `algorithm=sha1;version=1.1`, into its version and algorithm.
+fn sign_description(sign: &str) -> (Option<&str>, Option<&str>) {
+    let mut version = None;
+    let mut algorithm = None;
+    for part in sign.split(';') {
+        match part.trim().split_once('=') {
+            Some(("version", v)) => version = Some(v),
+            Some(("algorithm", a)) => algorithm = Some(a),
+            _ => (),
+        }
+    }
+    (version, algorithm)
+}
+
+impl RequestVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how far the request timestamp may be from the current time; defaults to 15
+    /// minutes.
+    pub fn max_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Verifies a request, given its method, path (without the query string), headers and body.
+    ///
+    /// `key` is called with the client or user named in `X-Ops-Userid` to look up their public
+    /// key, once the headers have been checked. Any error it returns is returned as is.
+    pub fn verify<F>(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &HeaderMap,
+        body: &str,
+        key: F,
+    ) -> Result<VerifiedRequest, Error>
+    where
+        F: FnOnce(&str) -> Result<PKey<Public>, Error>,
+    {
+        self.verify_at(method, path, headers, body, key, Utc::now())
+    }
+
+    /// Like `verify`, but checks the timestamp against `now` rather than the current time.
+    pub fn verify_at<F>(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &HeaderMap,
+        body: &str,
+        key: F,
+        now: DateTime<Utc>,
+    ) -> Result<VerifiedRequest, Error>
+    where
+        F: FnOnce(&str) -> Result<PKey<Public>, Error>,
+    {
+        let userid = header(headers, "X-Ops-Userid")?;
+        let date = header(headers, "X-Ops-Timestamp")?;
+        let content_hash = header(headers, "X-Ops-Content-Hash")?;
+        let signature = general_purpose::STANDARD
+            .decode(authorization(headers)?)
+            .map_err(|_| invalid("X-Ops-Authorization is not valid base64"))?;
+        let method = method.to_ascii_uppercase();
+        let path = squeeze_path(path);
+
+        let timestamp = DateTime::parse_from_rfc3339(date)
+            .map_err(|_| invalid("unparseable X-Ops-Timestamp"))?
+            .with_timezone(&Utc);
+        if (now - timestamp).abs() > self.max_skew {
+            return Err(invalid(&format!(
+                "timestamp {} is too far from the current time {}",
+                timestamp.format("%Y-%m-%dT%H:%M:%SZ"),
+                now.format("%Y-%m-%dT%H:%M:%SZ")
+            )));
+        }
+
+        let (version, algorithm) = sign_description(header(headers, "X-Ops-Sign")?);
+        let unsupported = || {
+            invalid(&format!(
+                "unsupported signing protocol version {} with algorithm {}",
+                version.unwrap_or("(none)"),
+                algorithm.unwrap_or("(none)")
+            ))
+        };
+        let version: SignVersion = version
+            .ok_or_else(unsupported)?
+            .parse()
+            .map_err(|_| unsupported())?;
+        let algorithm: SignAlgorithm = algorithm
+            .unwrap_or("sha1")
+            .parse()
+            .map_err(|_| unsupported())?;
+
+        let key = key(userid)?;
+        match (version, algorithm) {
+            (SignVersion::V1_0, SignAlgorithm::Sha1) | (SignVersion::V1_1, SignAlgorithm::Sha1) => {
+                if content_hash != auth11::content_hash(body)? {
+                    return Err(invalid("content hash does not match the body"));
+                }
+                let expected = if version == SignVersion::V1_0 {
+                    auth10::canonical_request(&method, &path, content_hash, date, userid)?
+                } else {
+                    auth11::canonical_request(&method, &path, content_hash, date, userid)?
+                };
+                let rsa = key.rsa()?;
+                let mut decrypted = vec![0; rsa.size() as usize];
+                let len = rsa
+                    .public_decrypt(&signature, &mut decrypted, Padding::PKCS1)
+                    .map_err(|_| invalid("signature does not match"))?;
+                if &decrypted[..len] != expected.as_bytes() {
+                    return Err(invalid("signature does not match"));
+                }
+            }
+            (SignVersion::V1_3, _) => {
+                if content_hash != auth13::content_hash(body, algorithm)? {
+                    return Err(invalid("content hash does not match the body"));
+                }
+                let api_version = header(headers, "X-Ops-Server-API-Version").unwrap_or("0");
+                let expected = auth13::canonical_request(
+                    &method,
+                    &path,
+                    content_hash,
+                    date,
+                    userid,
+                    api_version,
+                );
+                let digest = match algorithm {
+                    SignAlgorithm::Sha1 => MessageDigest::sha1(),
+                    SignAlgorithm::Sha256 => MessageDigest::sha256(),
+                };
+                let mut verifier = Verifier::new(digest, &key)?;
+                verifier.update(expected.as_bytes())?;
+                if !verifier.verify(&signature).unwrap_or(false) {
+                    return Err(invalid("signature does not match"));
+                }
+            }
+            _ => return Err(unsupported()),
+        }
+
+        Ok(VerifiedRequest {
+            userid: userid.into(),
+            version,
+            algorithm,
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authentication::auth10::Auth10;
+    use crate::authentication::auth11::Auth11;
+    use crate::authentication::auth13::Auth13;
+    use crate::authentication::signer::PemSigner;
+    use hyper::header::HeaderValue;
+    use std::fs;
+    use std::rc::Rc;
+
+    const PATH: &str = "/organizations/clownco";
+    const BODY: &str = "Spec Body";
+    const USER: &str = "spec-user";
+    const PRIVATE_KEY: &str = "fixtures/spec-user.pem";
+
+    /// Returns a signer for `USER`, and a key lookup that only knows `USER`.
+    fn keys() -> (Rc<PemSigner>, impl Fn(&str) -> Result<PKey<Public>, Error>) {
+        let pem = fs::read(PRIVATE_KEY).unwrap();
+        let private = PKey::private_key_from_pem(&pem).unwrap();
+        let public = PKey::public_key_from_pem(&private.public_key_to_pem().unwrap()).unwrap();
+        let lookup = move |userid: &str| {
+            if userid == USER {
+                Ok(public.clone())
+            } else {
+                Err(failure::err_msg(format!("no such user {}", userid)))
+            }
+        };
+        (Rc::new(PemSigner::new(private)), lookup)
+    }
+
+    #[test]
+    fn test_verify_auth13() {
+        let (signer, key) = keys();
+        let mut headers = HeaderMap::new();
+        Auth13::new(PATH, signer, "post", USER, "1", Some(BODY.into()))
+            .build(&mut headers)
+            .unwrap();
+        headers.insert("X-Ops-Server-API-Version", HeaderValue::from(1));
+
+        let verifier = RequestVerifier::new();
+        let request = verifier.verify("POST", PATH, &headers, BODY, &key).unwrap();
+        assert_eq!(request.userid, USER);
+        assert_eq!(request.version, SignVersion::V1_3);
+
+        assert!(verifier
+            .verify("POST", PATH, &headers, "Other Body", &key)
+            .is_err());
+        assert!(verifier
+            .verify("POST", "/organizations/other", &headers, BODY, &key)
+            .is_err());
+        let later = Utc::now() + Duration::minutes(20);
+        assert!(verifier
+            .verify_at("POST", PATH, &headers, BODY, &key, later)
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_auth11() {
+        let (signer, key) = keys();
+        let mut headers = HeaderMap::new();
+        Auth11::new(PATH, signer, "post", USER, "1", Some(BODY.into()))
+            .build(&mut headers)
+            .unwrap();
+
+        let verifier = RequestVerifier::new();
+        let request = verifier.verify("POST", PATH, &headers, BODY, &key).unwrap();
+        assert_eq!(request.version, SignVersion::V1_1);
+
+        assert!(verifier.verify("PUT", PATH, &headers, BODY, &key).is_err());
+        headers.insert("X-Ops-Userid", HeaderValue::from_static("someone-else"));
+        let err = verifier
+            .verify("POST", PATH, &headers, BODY, &key)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "no such user someone-else");
+        let err = verifier
+            .verify("POST", PATH, &headers, BODY, |_: &str| key(USER))
+            .unwrap_err();
+        assert!(err.downcast_ref::<ChefError>().is_some());
+    }
+
+    #[test]
+    fn test_verify_auth10_and_auth13_sha1() {
+        let (signer, key) = keys();
+        let verifier = RequestVerifier::new();
+
+        let mut headers = HeaderMap::new();
+        Auth10::new(PATH, signer.clone(), "get", USER, None)
+            .build(&mut headers)
+            .unwrap();
+        let request = verifier.verify("GET", PATH, &headers, "", &key).unwrap();
+        assert_eq!(request.version, SignVersion::V1_0);
+
+        let mut headers = HeaderMap::new();
+        Auth13::new(PATH, signer, "post", USER, "1", Some(BODY.into()))
+            .algorithm(SignAlgorithm::Sha1)
+            .build(&mut headers)
+            .unwrap();
+        headers.insert("X-Ops-Server-API-Version", HeaderValue::from(1));
+        let request = verifier.verify("POST", PATH, &headers, BODY, &key).unwrap();
+        assert_eq!(request.version, SignVersion::V1_3);
+        assert_eq!(request.algorithm, SignAlgorithm::Sha1);
+    }
+
+    #[test]
+    fn test_verify_unsupported_protocol() {
+        let (signer, key) = keys();
+        let verifier = RequestVerifier::new();
+        let mut headers = HeaderMap::new();
+        Auth13::new(PATH, signer, "post", USER, "1", Some(BODY.into()))
+            .build(&mut headers)
+            .unwrap();
+
+        for sign in &["algorithm=sha256;version=2.0", "algorithm=md5;version=1.3"] {
+            headers.insert("X-Ops-Sign", HeaderValue::from_static(sign));
+            let err = verifier
+                .verify("POST", PATH, &headers, BODY, &key)
+                .unwrap_err();
+            match err.downcast_ref::<ChefError>() {
+                Some(ChefError::RequestVerificationError(reason)) => {
+                    assert!(reason.starts_with("unsupported signing protocol"))
+                }
+                e => panic!("unexpected error {:?}", e),
+            }
+        }
+    }
+}
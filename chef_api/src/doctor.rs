@@ -0,0 +1,269 @@
+//! Checks credentials profiles for problems, reporting each one as a `Diagnostic` that says
+//! what is wrong and how to fix it.
+//!
+//! ```rust,no_run
+//! for diagnostic in chef_api::doctor::doctor().unwrap() {
+//!     println!("{}", diagnostic);
+//! }
+//! ```
+
+use crate::api_client::ApiClient;
+use crate::credentials::{profile_names, read_credentials, Config};
+use crate::errors::ChefError;
+use failure::Error;
+use std::fmt;
+
+/// The part of a profile a `Diagnostic` is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// The profile could not be read from the credentials file.
+    Profile,
+    /// The `chef_server_url` setting, or the organization it names.
+    ServerUrl,
+    /// The `client_name` or `node_name` setting.
+    ClientName,
+    /// The `client_key` setting, or the key it refers to.
+    ClientKey,
+    /// The `trusted_certs_dir` setting, or the certificates in it.
+    TrustedCerts,
+    /// Reaching the Chef Server.
+    Connection,
+    /// The Chef Server's clock differs too much from the local clock.
+    ClockSkew,
+    /// The Chef Server rejected the signed test request.
+    Authentication,
+}
+
+/// A problem found with a profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The profile the problem was found in.
+    pub profile: String,
+    /// What was being checked.
+    pub check: Check,
+    /// What is wrong.
+    pub message: String,
+    /// How to fix it.
+    pub suggestion: String,
+}
+
+impl Diagnostic {
+    pub fn new(profile: &str, check: Check, message: String, suggestion: String) -> Self {
+        Self {
+            profile: profile.into(),
+            check,
+            message,
+            suggestion,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}; {}", self.profile, self.message, self.suggestion)
+    }
+}
+
+/// Checks every profile in `~/.chef/credentials`, including a test request to the Chef Server
+/// for each profile without configuration problems. Returns an empty list if all is well.
+///
+/// Settings from the environment are ignored, so each profile is checked as written.
+pub fn doctor() -> Result<Vec<Diagnostic>, Error> {
+    let (path, credentials) = match read_credentials() {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            return Ok(vec![Diagnostic::new(
+                "~/.chef/credentials",
+                Check::Profile,
+                e.to_string(),
+                String::from("create ~/.chef/credentials as described in Chef RFC 99"),
+            )])
+        }
+    };
+    let profiles = match profile_names(&credentials) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            return Ok(vec![Diagnostic::new(
+                &path,
+                Check::Profile,
+                e.to_string(),
+                format!("fix the TOML syntax of {}", path),
+            )])
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    for profile in profiles {
+        match Config::from_str(&credentials, &profile) {
+            Ok(config) => diagnostics.extend(profile_diagnostics(config)),
+            Err(e) => diagnostics.push(Diagnostic::new(
+                &profile,
+                Check::Profile,
+                e.to_string(),
+                format!("fix the {} profile in {}", profile, path),
+            )),
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// The name of the organization in `config`'s `chef_server_url`, if it names one.
+fn organization_name(config: &Config) -> Option<String> {
+    config
+        .organization_path()
+        .ok()?
+        .strip_prefix("/organizations/")
+        .map(String::from)
+}
+
+/// Checks a single profile, as `ApiClient::validate` does. Problems that prevent creating a
+/// client at all are reported as diagnostics too.
+fn profile_diagnostics(config: Config) -> Vec<Diagnostic> {
+    match ApiClient::new(config.clone()) {
+        Ok(client) => client.validate(),
+        Err(e) => {
+            let diagnostics = config.validate();
+            if diagnostics.is_empty() {
+                vec![Diagnostic::new(
+                    config.profile(),
+                    Check::Connection,
+                    format!("unable to set up a connection: {}", e),
+                    String::from("check the TLS settings of the profile"),
+                )]
+            } else {
+                diagnostics
+            }
+        }
+    }
+}
+
+/// Describes the outcome of the test request made by `ApiClient::validate`, given the status
+/// it returned or the error that prevented it.
+pub(crate) fn request_diagnostic(
+    config: &Config,
+    path: &str,
+    result: Result<u16, Error>,
+) -> Option<Diagnostic> {
+    let profile = config.profile();
+    let name = config.client_name().unwrap_or_default();
+    let url = &config.chef_server_url;
+    let (check, message, suggestion) = match result {
+        Ok(status) if (200..300).contains(&status) => return None,
+        // The signature was accepted, but the client can't read the organization.
+        Ok(403) => match organization_name(config) {
+            Some(org) => (
+                Check::ServerUrl,
+                format!("{} is not a member of organization {}", name, org),
+                format!(
+                    "check the organization name in chef_server_url, or ask an administrator \
+                     of {} to add {}",
+                    org, name
+                ),
+            ),
+            None => (
+                Check::Authentication,
+                format!("{} is not allowed to read {}", name, path),
+                format!("check that {} is the right client for {}", name, url),
+            ),
+        },
+        Ok(401) => (
+            Check::Authentication,
+            format!("the Chef Server rejected the signature of {}", name),
+            format!(
+                "check that the client key is the current key of {} in {}",
+                name, url
+            ),
+        ),
+        Ok(404) => (
+            Check::ServerUrl,
+            format!("{} was not found on the Chef Server", path),
+            String::from("check the organization name in chef_server_url"),
+        ),
+        Ok(status) => (
+            Check::Connection,
+            format!("the Chef Server returned status {} for {}", status, path),
+            format!("check that {} is a Chef Server", url),
+        ),
+        Err(e) => match e.downcast_ref::<ChefError>() {
+            Some(ChefError::ClockSkewError(skew)) => (
+                Check::ClockSkew,
+                format!(
+                    "the local clock is {} seconds off from the Chef Server's",
+                    skew
+                ),
+                String::from("synchronize the local clock, for example with NTP"),
+            ),
+            _ => (
+                Check::Connection,
+                format!("unable to reach {}: {}", url, e),
+                String::from("check chef_server_url and the network connection"),
+            ),
+        },
+    };
+    Some(Diagnostic::new(profile, check, message, suggestion))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_diagnostics() {
+        let mut config = Config::from_knife_config(Some("fixtures/knife.rb")).unwrap();
+        config.trusted_certs_dir = Some(String::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/bad_certs"
+        )));
+        let diagnostics = profile_diagnostics(config);
+        let diagnostic = diagnostics
+            .iter()
+            .find(|d| d.check == Check::TrustedCerts)
+            .unwrap();
+        assert!(diagnostic.message.contains("broken.crt"));
+    }
+
+    #[test]
+    fn test_request_diagnostic() {
+        let config = Config::builder()
+            .chef_server_url("https://api.chef.io/organizations/bedrock")
+            .client_name("barney")
+            .client_key("barney.pem")
+            .build()
+            .unwrap();
+        let path = "/organizations/bedrock/environments/_default";
+        assert_eq!(request_diagnostic(&config, path, Ok(200)), None);
+
+        let diagnostic = request_diagnostic(&config, path, Ok(403)).unwrap();
+        assert_eq!(diagnostic.check, Check::ServerUrl);
+        assert_eq!(
+            diagnostic.message,
+            "barney is not a member of organization bedrock"
+        );
+
+        let server = Config::builder()
+            .chef_server_url("https://api.chef.io")
+            .client_name("barney")
+            .client_key("barney.pem")
+            .build()
+            .unwrap();
+        assert_eq!(
+            request_diagnostic(&server, "/users/barney", Ok(403))
+                .unwrap()
+                .check,
+            Check::Authentication
+        );
+
+        let diagnostic = request_diagnostic(&config, path, Ok(401)).unwrap();
+        assert_eq!(diagnostic.profile, "default");
+        assert_eq!(diagnostic.check, Check::Authentication);
+        assert_eq!(
+            request_diagnostic(&config, path, Ok(404)).unwrap().check,
+            Check::ServerUrl
+        );
+        let skewed = Err(ChefError::ClockSkewError(-1200).into());
+        assert_eq!(
+            request_diagnostic(&config, path, skewed).unwrap().check,
+            Check::ClockSkew
+        );
+    }
+}
@@ -1,3 +1,6 @@
+// failure_derive generates its impls inside an anonymous const.
+#![allow(non_local_definitions)]
+
 use hyper;
 use openssl;
 use serde_json;
@@ -49,4 +52,68 @@ pub enum ChefError {
         _0
     )]
     DuplicateClientNameError(String),
+    #[fail(display = "No profile named {} in the credentials file", _0)]
+    MissingProfileError(String),
+    #[fail(display = "Can't read trusted certificates from {}: {}", _0, _1)]
+    TrustedCertificateError(String, String),
+    #[fail(display = "Can't set attribute {}: {}", _0, _1)]
+    AttributePathError(String, String),
+    #[fail(display = "Failed to authenticate using the new key {}: {}", _0, _1)]
+    KeyVerificationError(String, String),
+    #[fail(display = "Failed to decrypt data bag item: {}", _0)]
+    DecryptionError(String),
+    #[fail(display = "Data bag item HMAC does not match; the secret is most likely incorrect")]
+    HmacMismatchError,
+    #[fail(
+        display = "Data bag item authentication tag does not match; the secret is most likely incorrect"
+    )]
+    AuthTagMismatchError,
+    #[fail(
+        display = "Invalid data bag name {}; only letters, numbers, '.', '-' and '_' are allowed",
+        _0
+    )]
+    InvalidDataBagNameError(String),
+    #[fail(
+        display = "Invalid data bag item id {}; only letters, numbers, '.', '-' and '_' are allowed",
+        _0
+    )]
+    InvalidDataBagItemIdError(String),
+    #[fail(
+        display = "Data bag item {} has a mismatched id of {} in its data",
+        _0, _1
+    )]
+    DataBagItemIdMismatchError(String, String),
+    #[fail(display = "Invalid run list item: {}", _0)]
+    InvalidRunListItemError(String),
+    #[fail(display = "Invalid version {}; expected x.y or x.y.z", _0)]
+    InvalidVersionError(String),
+    #[fail(display = "Invalid version constraint: {}", _0)]
+    InvalidVersionConstraintError(String),
+    #[fail(
+        display = "Unsupported signing protocol version {}; expected 1.0, 1.1 or 1.3",
+        _0
+    )]
+    InvalidSignVersionError(String),
+    #[fail(
+        display = "Unsupported signing algorithm {}; expected sha1 or sha256",
+        _0
+    )]
+    InvalidSignAlgorithmError(String),
+    #[fail(
+        display = "The Chef Server rejected the request, and the local clock is {} seconds off \
+                   from the server's, beyond its 15 minute limit; later requests will correct \
+                   for this, but the local clock should be fixed",
+        _0
+    )]
+    ClockSkewError(i64),
+    #[fail(display = "Request failed authentication: {}", _0)]
+    RequestVerificationError(String),
+    #[fail(display = "Unable to solve cookbook dependencies: {}", message)]
+    DepsolverError {
+        message: String,
+        unsatisfiable_run_list_item: Option<String>,
+        non_existent_cookbooks: Vec<String>,
+        cookbooks_with_no_versions: Vec<String>,
+        most_constrained_cookbooks: Vec<String>,
+    },
 }
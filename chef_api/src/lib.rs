@@ -59,6 +59,8 @@ pub mod errors;
 #[macro_use]
 mod macros;
 pub mod credentials;
+pub mod doctor;
+mod ruby_config;
 pub mod utils;
 
 pub mod api_client;
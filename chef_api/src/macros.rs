@@ -1,7 +1,7 @@
 macro_rules! build {
     ($name:ident, $type:ident) => {
         #[doc = "Generate a new $type request."]
-        pub fn $name(&self) -> $type {
+        pub fn $name(&self) -> $type<'_> {
             self.into()
         }
     };
@@ -11,14 +11,20 @@ macro_rules! import {
     () => {
         use failure::Error;
         use $crate::api_client::*;
+        use $crate::authentication::auth10::Auth10;
         use $crate::authentication::auth11::Auth11;
         use $crate::authentication::auth13::Auth13;
+        use $crate::authentication::signer::{PemSigner, Signer};
+        use $crate::authentication::SignVersion;
         use $crate::credentials::Config;
-        use $crate::utils::add_path_element;
+        use $crate::utils::{add_path_element, compensate_clock_skew};
+
+        use chrono::{Duration, Utc};
 
         use serde::Serialize;
         use serde_json;
 
+        use std::cell::Cell;
         use std::rc::Rc;
 
         use tokio::runtime::Runtime;
@@ -73,6 +79,14 @@ macro_rules! acls {
             self
         }
 
+        /// Request the granular form of the ACL, which lists users and clients separately
+        /// rather than combining them as actors.
+        pub fn granular(&mut self) -> &mut Self {
+            self.params
+                .push((String::from("detail"), String::from("granular")));
+            self
+        }
+
         /// Modify the given permission on the object.
         pub fn permission(&mut self, permission: &str) -> &mut Self {
             self.path = add_path_element(self.path.clone(), permission);
@@ -87,9 +101,12 @@ macro_rules! request_type {
         pub struct $n<'c> {
             pub(crate) client: &'c Rc<HyperClient<HttpsConnector<HttpConnector>>>,
             pub(crate) config: &'c Config,
+            pub(crate) signer: &'c Option<Rc<dyn Signer>>,
+            pub(crate) clock_offset: &'c Rc<Cell<i64>>,
             pub(crate) path: String,
             pub(crate) api_version: String,
             pub(crate) q: Option<String>,
+            pub(crate) params: Vec<(String, String)>,
         }
     };
 }
@@ -104,9 +121,12 @@ macro_rules! requests {
                 Self {
                     config: &api.config,
                     client: &api.client,
+                    signer: &api.signer,
+                    clock_offset: &api.clock_offset,
                     path,
                     api_version: String::from("1"),
                     q: None,
+                    params: Vec::new(),
                 }
             }
         }
@@ -122,9 +142,12 @@ macro_rules! requests {
                 Self {
                     config: &api.config,
                     client: &api.client,
+                    signer: &api.signer,
+                    clock_offset: &api.clock_offset,
                     path,
                     api_version: String::from("1"),
                     q: None,
+                    params: Vec::new(),
                 }
             }
         }
@@ -141,9 +164,12 @@ macro_rules! requests {
                 Self {
                     config: &api.config,
                     client: &api.client,
+                    signer: &api.signer,
+                    clock_offset: &api.clock_offset,
                     path,
                     api_version: String::from("1"),
                     q: None,
+                    params: Vec::new(),
                 }
             }
         }
@@ -164,13 +190,16 @@ macro_rules! execute {
             }
 
             #[doc(hidden)]
-            fn execute<B>(&self, body: Option<B>, method: &str) -> Result<Value, Error>
+            fn execute_raw<B>(&self, body: Option<B>, method: &str) -> Result<(u16, Value), Error>
             where
                 B: Serialize,
             {
                 let userid = self.config.client_name()?;
-                let key = self.config.key()?;
-                let sign_ver = self.config.sign_ver.clone();
+                let signer: Rc<dyn Signer> = match self.signer {
+                    Some(ref signer) => signer.clone(),
+                    None => Rc::new(PemSigner::new(self.config.private_key()?)),
+                };
+                let sign_ver = self.config.sign_ver;
                 let path = self.path.clone();
                 let api_version = self.api_version.clone();
 
@@ -179,6 +208,9 @@ macro_rules! execute {
                     url.query_pairs_mut()
                         .append_pair("q", self.q.as_ref().unwrap());
                 }
+                for (key, value) in &self.params {
+                    url.query_pairs_mut().append_pair(key, value);
+                }
 
                 let mth = match method {
                     "put" => Method::PUT,
@@ -195,25 +227,36 @@ macro_rules! execute {
                     None => serde_json::to_string("")?,
                 };
 
-                match sign_ver.as_str() {
-                    "1.1" => Auth11::new(
+                // Sign with the Chef Server's idea of the current time.
+                let signed_at = Utc::now() + Duration::seconds(self.clock_offset.get());
+                let headers = req_builder.headers_mut().unwrap();
+                match sign_ver {
+                    SignVersion::V1_0 => {
+                        Auth10::new(&path, signer, method, &userid, Some(body.clone()))
+                            .timestamp(signed_at)
+                            .build(headers)?
+                    }
+                    SignVersion::V1_1 => Auth11::new(
                         &path,
-                        &key,
+                        signer,
                         method,
                         &userid,
                         &api_version,
-                        Some(body.clone().into()),
+                        Some(body.clone()),
                     )
-                    .build(req_builder.headers_mut().unwrap())?,
-                    _ => Auth13::new(
+                    .timestamp(signed_at)
+                    .build(headers)?,
+                    SignVersion::V1_3 => Auth13::new(
                         &path,
-                        &key,
+                        signer,
                         method,
                         &userid,
                         &api_version,
-                        Some(body.clone().into()),
+                        Some(body.clone()),
                     )
-                    .build(req_builder.headers_mut().unwrap())?,
+                    .algorithm(self.config.sign_algorithm)
+                    .timestamp(signed_at)
+                    .build(headers)?,
                 };
 
                 req_builder.headers_mut().map(|h| {
@@ -237,6 +280,7 @@ macro_rules! execute {
                 let request = req_builder.body(body.into())?;
 
                 let client = self.client;
+                let clock_offset = self.clock_offset;
                 let resp = async {
                     let res = client
                         .request(request)
@@ -245,20 +289,28 @@ macro_rules! execute {
                     debug!("Status is {:?}", res.status());
 
                     let status = res.status();
+                    let received_at = Utc::now() + Duration::seconds(clock_offset.get());
+                    compensate_clock_skew(
+                        &clock_offset,
+                        res.headers().get(header::DATE),
+                        signed_at,
+                        received_at,
+                        status.as_u16(),
+                    )?;
+
                     let body = hyper::body::to_bytes(res.into_body())
                         .await
                         .map_err(ChefError::HTTPError)?;
 
                     trace!("{}", String::from_utf8_lossy(&body));
 
-                    let body: Value =
-                        serde_json::from_slice(&body).map_err(ChefError::JsonError)?;
-
-                    if status.is_success() {
-                        Ok(body)
+                    let body: Value = if status.is_success() {
+                        serde_json::from_slice(&body).map_err(ChefError::JsonError)?
                     } else {
-                        Err(ChefError::ChefServerResponseError(status.as_u16()))
-                    }
+                        serde_json::from_slice(&body).unwrap_or(Value::Null)
+                    };
+
+                    Ok::<_, ChefError>((status.as_u16(), body))
                 };
 
                 let rt = Runtime::new()?;
@@ -5,7 +5,13 @@ impl<'c> EnvironmentsQuery<'c> {
     path!(environment);
     path!(-> cookbooks);
     path!(cookbook);
-    path!(-> cookbook_versions);
+    path!(
+        /// Solve the cookbook dependencies of a run list in an environment.
+        ///
+        /// Can only be called once an environment has been specified with `environment()`.
+        ///
+        /// - POST: Resolve a run list, given as `{"run_list": [...]}`
+        -> cookbook_versions);
     path!(-> nodes);
     path!(-> recipes);
     path!(-> roles);
@@ -3,5 +3,42 @@ requests!(root ServerOrganizationsQuery, organizations);
 
 impl<'c> ServerOrganizationsQuery<'c> {
     path!(organization);
+    path!(
+        /// The users of an organization.
+        ///
+        /// Can only be called once an organization has been specified with `organization()`.
+        ///
+        /// - GET: Retrieve the list of users in the organization
+        /// - POST: Add a user to the organization
+        ///
+        /// ```rust,no_run
+        /// # use chef_api::api_client::*;
+        /// # let api = ApiClient::from_credentials(None).unwrap();
+        /// let users = api.server_organizations().organization("bedrock").users().get();
+        /// ```
+        -> users);
+    path!(
+        /// Operate on a user of an organization.
+        ///
+        /// - GET: Retrieve the user
+        /// - DELETE: Remove the user from the organization
+        user
+    );
+
+    path!(
+        /// Invitations for users to join an organization.
+        ///
+        /// Can only be called once an organization has been specified with `organization()`.
+        ///
+        /// - GET: Retrieve the list of pending invitations
+        /// - POST: Invite a user to the organization
+        -> association_requests);
+    path!(
+        /// Operate on a pending invitation.
+        ///
+        /// - DELETE: Cancel the invitation
+        association_request
+    );
+
     acls!();
 }
@@ -11,4 +11,16 @@ impl<'c> ServerUsersQuery<'c> {
     path!(association_request);
 
     acls!();
+
+    /// Only list users with the given email address.
+    ///
+    /// ```rust,no_run
+    /// # use chef_api::api_client::*;
+    /// # let api = ApiClient::from_credentials(None).unwrap();
+    /// let users = api.server_users().email("barney@bedrock.com").get();
+    /// ```
+    pub fn email(&mut self, email: &str) -> &mut Self {
+        self.params.push((String::from("email"), email.to_owned()));
+        self
+    }
 }
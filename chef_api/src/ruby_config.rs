@@ -0,0 +1,494 @@
+//! A reader for the common subset of Ruby `knife.rb` and `config.rb` files.
+//!
+//! Only literal settings are understood, e.g. `node_name "barney"` or
+//! `client_key File.join(current_dir, "barney.pem")`, along with local variables, string
+//! interpolation, `__dir__`, `__FILE__`, `File.join`, `File.dirname`, `File.expand_path`,
+//! `Dir.home` and `ENV[...]`. Anything else is skipped, unless it sets one of the settings this
+//! crate uses, in which case it is an error.
+
+use crate::errors::ChefError;
+use failure::Error;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// The settings that must be understood if present.
+const KNOWN_SETTINGS: &[&str] = &[
+    "chef_server_url",
+    "node_name",
+    "client_key",
+    "validation_key",
+    "ssl_verify_mode",
+    "trusted_certs_dir",
+    "http_proxy",
+    "https_proxy",
+    "no_proxy",
+    "authentication_protocol_version",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RubyValue {
+    Str(String),
+    Symbol(String),
+    Int(i64),
+    Bool(bool),
+    Nil,
+}
+
+/// Evaluates the settings in `source`, which was read from `file`.
+pub(crate) fn parse(source: &str, file: &Path) -> Result<HashMap<String, RubyValue>, Error> {
+    let mut vars = HashMap::new();
+    let mut settings = HashMap::new();
+
+    for (n, line) in source.lines().enumerate() {
+        let mut parser = Parser {
+            chars: line.chars().collect(),
+            pos: 0,
+            vars: &vars,
+            file,
+        };
+        let statement = match parser.statement() {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(ChefError::UnparseableConfigError(format!(
+                    "{}:{}: {}",
+                    file.display(),
+                    n + 1,
+                    e
+                ))
+                .into())
+            }
+        };
+        match statement {
+            Statement::Assign(name, value) => {
+                vars.insert(name, value);
+            }
+            Statement::Setting(name, value) => {
+                settings.insert(name, value);
+            }
+            Statement::Unknown(Some(name), reason) if KNOWN_SETTINGS.contains(&name.as_str()) => {
+                return Err(ChefError::UnparseableConfigError(format!(
+                    "{}:{}: unable to evaluate {}: {}",
+                    file.display(),
+                    n + 1,
+                    name,
+                    reason
+                ))
+                .into())
+            }
+            Statement::Unknown(_, reason) => {
+                debug!("Skipping {}:{}: {}", file.display(), n + 1, reason)
+            }
+            Statement::Empty => (),
+        }
+    }
+    Ok(settings)
+}
+
+#[derive(Debug)]
+enum Statement {
+    Empty,
+    Assign(String, RubyValue),
+    Setting(String, RubyValue),
+    Unknown(Option<String>, String),
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    vars: &'a HashMap<String, RubyValue>,
+    file: &'a Path,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).cloned()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// True at the end of the line or the start of a comment.
+    fn at_end(&mut self) -> bool {
+        self.skip_ws();
+        matches!(self.peek(), None | Some('#') | Some(';'))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", c))
+        }
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        self.skip_ws();
+        let start = self.pos;
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return None;
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        Some(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn statement(&mut self) -> Result<Statement, String> {
+        if self.at_end() {
+            return Ok(Statement::Empty);
+        }
+        let name = match self.ident() {
+            Some(name) => name,
+            None => return Ok(Statement::Unknown(None, String::from("not a setting"))),
+        };
+
+        self.skip_ws();
+        let assign = self.peek() == Some('=') && !matches!(self.peek_at(1), Some('=') | Some('~'));
+        if assign {
+            self.pos += 1;
+        } else if matches!(self.peek(), Some('.') | Some('['))
+            || (self.peek() == Some(':') && self.peek_at(1) == Some(':'))
+        {
+            return Ok(Statement::Unknown(
+                Some(name),
+                String::from("not a setting"),
+            ));
+        } else if self.at_end() {
+            return Ok(Statement::Unknown(
+                Some(name),
+                String::from("no value given"),
+            ));
+        }
+
+        let value = match self.expr() {
+            Ok(value) if self.at_end() => value,
+            Ok(_) => {
+                return Ok(Statement::Unknown(
+                    Some(name),
+                    String::from("unsupported expression"),
+                ))
+            }
+            Err(e) => return Ok(Statement::Unknown(Some(name), e)),
+        };
+        if assign {
+            Ok(Statement::Assign(name, value))
+        } else {
+            Ok(Statement::Setting(name, value))
+        }
+    }
+
+    fn args(&mut self) -> Result<Vec<RubyValue>, String> {
+        self.expect('(')?;
+        let mut args = Vec::new();
+        if self.eat(')') {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expr()?);
+            if self.eat(')') {
+                return Ok(args);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn string_args(&mut self) -> Result<Vec<String>, String> {
+        self.args()?
+            .into_iter()
+            .map(|a| match a {
+                RubyValue::Str(s) => Ok(s),
+                other => Err(format!("expected a string, found {:?}", other)),
+            })
+            .collect()
+    }
+
+    fn expr(&mut self) -> Result<RubyValue, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('\'') => self.single_quoted(),
+            Some('"') => self.double_quoted(),
+            Some(':') => {
+                self.pos += 1;
+                self.ident()
+                    .map(RubyValue::Symbol)
+                    .ok_or_else(|| String::from("invalid symbol"))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expr()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' => {
+                let start = self.pos;
+                self.pos += 1;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '_') {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos]
+                    .iter()
+                    .filter(|&&c| c != '_')
+                    .collect();
+                number
+                    .parse()
+                    .map(RubyValue::Int)
+                    .map_err(|_| format!("invalid number {}", number))
+            }
+            Some(_) => {
+                let ident = self
+                    .ident()
+                    .ok_or_else(|| String::from("unsupported expression"))?;
+                self.call(&ident)
+            }
+            None => Err(String::from("expected a value")),
+        }
+    }
+
+    fn call(&mut self, ident: &str) -> Result<RubyValue, String> {
+        match ident {
+            "nil" => Ok(RubyValue::Nil),
+            "true" => Ok(RubyValue::Bool(true)),
+            "false" => Ok(RubyValue::Bool(false)),
+            "__FILE__" => Ok(RubyValue::Str(self.file.display().to_string())),
+            "__dir__" => Ok(RubyValue::Str(dirname(&self.file.display().to_string()))),
+            "ENV" => {
+                let name = if self.eat('[') {
+                    let name = self.expr()?;
+                    self.expect(']')?;
+                    name
+                } else if self.eat('.') && self.ident().as_deref() == Some("fetch") {
+                    let mut args = self.args()?;
+                    if args.is_empty() {
+                        return Err(String::from("ENV.fetch needs a name"));
+                    }
+                    args.remove(0)
+                } else {
+                    return Err(String::from("unsupported use of ENV"));
+                };
+                match name {
+                    RubyValue::Str(name) => {
+                        Ok(env::var(name).map(RubyValue::Str).unwrap_or(RubyValue::Nil))
+                    }
+                    _ => Err(String::from("ENV needs a string")),
+                }
+            }
+            "Dir" => {
+                self.expect('.')?;
+                match self.ident().as_deref() {
+                    Some("home") => dirs::home_dir()
+                        .map(|h| RubyValue::Str(h.display().to_string()))
+                        .ok_or_else(|| String::from("unable to find the home directory")),
+                    _ => Err(String::from("unsupported Dir method")),
+                }
+            }
+            "File" => {
+                self.expect('.')?;
+                let method = self.ident();
+                let args = self.string_args()?;
+                match (method.as_deref(), &args[..]) {
+                    (Some("join"), parts) => Ok(RubyValue::Str(join(parts))),
+                    (Some("dirname"), [path]) => Ok(RubyValue::Str(dirname(path))),
+                    (Some("expand_path"), [path]) => Ok(RubyValue::Str(expand_path(path, None))),
+                    (Some("expand_path"), [path, base]) => {
+                        Ok(RubyValue::Str(expand_path(path, Some(base))))
+                    }
+                    _ => Err(String::from("unsupported File method")),
+                }
+            }
+            name => self
+                .vars
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("unknown variable or method {}", name)),
+        }
+    }
+
+    fn single_quoted(&mut self) -> Result<RubyValue, String> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(String::from("unterminated string")),
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(RubyValue::Str(s));
+                }
+                Some('\\') if matches!(self.peek_at(1), Some('\'') | Some('\\')) => {
+                    s.push(self.peek_at(1).unwrap());
+                    self.pos += 2;
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn double_quoted(&mut self) -> Result<RubyValue, String> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(String::from("unterminated string")),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(RubyValue::Str(s));
+                }
+                Some('\\') => {
+                    let c = self
+                        .peek_at(1)
+                        .ok_or_else(|| String::from("unterminated string"))?;
+                    s.push(match c {
+                        'n' => '\n',
+                        't' => '\t',
+                        'e' => '\x1b',
+                        c => c,
+                    });
+                    self.pos += 2;
+                }
+                Some('#') if self.peek_at(1) == Some('{') => {
+                    self.pos += 2;
+                    match self.expr()? {
+                        RubyValue::Str(v) | RubyValue::Symbol(v) => s.push_str(&v),
+                        RubyValue::Int(i) => s.push_str(&i.to_string()),
+                        RubyValue::Bool(b) => s.push_str(&b.to_string()),
+                        RubyValue::Nil => (),
+                    }
+                    self.expect('}')?;
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Like Ruby's `File.join`, joins the parts with exactly one `/` between them.
+fn join(parts: &[String]) -> String {
+    let mut joined = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            joined.push_str(part.trim_end_matches('/'));
+        } else {
+            joined.push('/');
+            joined.push_str(part.trim_matches('/'));
+        }
+    }
+    joined
+}
+
+fn dirname(path: &str) -> String {
+    match Path::new(path).parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.display().to_string(),
+        _ => String::from("."),
+    }
+}
+
+fn expand_path(path: &str, base: Option<&str>) -> String {
+    let mut expanded = PathBuf::new();
+    if let Some(rest) = path.strip_prefix('~') {
+        expanded.push(dirs::home_dir().unwrap_or_default());
+        expanded.push(rest.trim_start_matches('/'));
+    } else {
+        match base {
+            Some(base) => expanded.push(expand_path(base, None)),
+            None => expanded.push(env::current_dir().unwrap_or_default()),
+        }
+        expanded.push(path);
+    }
+
+    // Resolve `.` and `..` lexically, as Ruby does.
+    let mut resolved = PathBuf::new();
+    for component in expanded.components() {
+        match component {
+            std::path::Component::CurDir => (),
+            std::path::Component::ParentDir => {
+                resolved.pop();
+            }
+            c => resolved.push(c),
+        }
+    }
+    resolved.display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = r##"
+            # See https://docs.chef.io/config_rb/
+            current_dir = File.dirname(__FILE__)
+            log_level                :info
+            node_name                "barney"
+            client_key               "#{current_dir}/barney.pem"
+            validation_key           File.join(current_dir, '/bedrock-validator.pem')
+            chef_server_url          'https://api.chef.io/organizations/bedrock' # the org
+            ssl_verify_mode          :verify_none
+            trusted_certs_dir        File.expand_path("../trusted_certs", __dir__)
+            http_proxy(nil)
+            cookbook_path            ["#{current_dir}/../cookbooks"]
+            knife[:editor] = "vim"
+        "##;
+        let settings = parse(source, Path::new("/home/barney/.chef/config.rb")).unwrap();
+        assert_eq!(settings["node_name"], RubyValue::Str("barney".into()));
+        assert_eq!(
+            settings["client_key"],
+            RubyValue::Str("/home/barney/.chef/barney.pem".into())
+        );
+        assert_eq!(
+            settings["validation_key"],
+            RubyValue::Str("/home/barney/.chef/bedrock-validator.pem".into())
+        );
+        assert_eq!(
+            settings["chef_server_url"],
+            RubyValue::Str("https://api.chef.io/organizations/bedrock".into())
+        );
+        assert_eq!(
+            settings["ssl_verify_mode"],
+            RubyValue::Symbol("verify_none".into())
+        );
+        assert_eq!(
+            settings["trusted_certs_dir"],
+            RubyValue::Str("/home/barney/trusted_certs".into())
+        );
+        assert_eq!(settings["http_proxy"], RubyValue::Nil);
+        assert!(!settings.contains_key("cookbook_path"));
+    }
+
+    #[test]
+    fn test_parse_unsupported_setting() {
+        let source = "client_key \"#{current_dir}/barney.pem\"\n";
+        assert!(parse(source, Path::new("knife.rb")).is_err());
+        let source = "node_name Chef::Config[:node_name] || 'barney'\n";
+        assert!(parse(source, Path::new("knife.rb")).is_err());
+    }
+}
@@ -1,6 +1,16 @@
 use crate::errors::ChefError;
+use chrono::{DateTime, Utc};
 use failure::Error;
+use hyper::header::HeaderValue;
 use serde_json::Value;
+use std::cell::Cell;
+
+/// The Chef Server rejects requests signed more than 15 minutes before or after its own time.
+pub const MAX_CLOCK_SKEW: i64 = 15 * 60;
+
+/// Differences between the local and server clocks smaller than this are put down to latency
+/// and the one second resolution of the `Date` header, and ignored.
+const CLOCK_SKEW_TOLERANCE: i64 = 60;
 
 pub fn expand_string(val: &Option<String>) -> String {
     match *val {
@@ -12,7 +22,7 @@ pub fn expand_string(val: &Option<String>) -> String {
 /// Remove duplicate and trailing slashes from a path
 pub fn squeeze_path(pth: &str) -> String {
     let mut st = String::new();
-    for p in pth.split('/').filter(|&x| x != "") {
+    for p in pth.split('/').filter(|&x| !x.is_empty()) {
         st.push('/');
         st.push_str(p)
     }
@@ -46,9 +56,92 @@ pub fn decode_list(data: &Value) -> Result<Vec<String>, Error> {
     }
 }
 
+/// Returns how many seconds the server's clock, as given by a response's `Date` header, is
+/// ahead of the local clock, if the difference is big enough to matter.
+///
+/// The server may have stamped the response at any point between `sent_at` and `received_at`,
+/// so it is compared with the midpoint of the two, and a slow response isn't mistaken for skew.
+pub(crate) fn clock_skew(
+    date: Option<&HeaderValue>,
+    sent_at: DateTime<Utc>,
+    received_at: DateTime<Utc>,
+) -> Option<i64> {
+    let date = date?.to_str().ok()?;
+    let server = DateTime::parse_from_rfc2822(date).ok()?;
+    let midpoint = sent_at + (received_at - sent_at) / 2;
+    let skew = (server.with_timezone(&Utc) - midpoint).num_seconds();
+    if skew.abs() > CLOCK_SKEW_TOLERANCE {
+        Some(skew)
+    } else {
+        None
+    }
+}
+
+/// Adds any clock skew shown by a response to `offset`, so later requests are signed with the
+/// server's time. Fails with a `ClockSkewError` if the skew is why the request was rejected.
+pub(crate) fn compensate_clock_skew(
+    offset: &Cell<i64>,
+    date: Option<&HeaderValue>,
+    sent_at: DateTime<Utc>,
+    received_at: DateTime<Utc>,
+    status: u16,
+) -> Result<(), ChefError> {
+    if let Some(skew) = clock_skew(date, sent_at, received_at) {
+        warn!(
+            "Local clock is {} seconds off from the Chef Server's; compensating",
+            skew
+        );
+        offset.set(offset.get() + skew);
+        if status == 401 && skew.abs() > MAX_CLOCK_SKEW {
+            return Err(ChefError::ClockSkewError(skew));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::squeeze_path;
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_clock_skew() {
+        let date = HeaderValue::from_static("Thu, 01 Jan 2009 12:20:00 GMT");
+        let sent_at = Utc.with_ymd_and_hms(2009, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(clock_skew(Some(&date), sent_at, sent_at), Some(1200));
+
+        let sent_at = Utc.with_ymd_and_hms(2009, 1, 1, 12, 19, 58).unwrap();
+        assert_eq!(clock_skew(Some(&date), sent_at, sent_at), None);
+        assert_eq!(clock_skew(None, sent_at, sent_at), None);
+
+        // A response that took five minutes isn't skew.
+        let sent_at = Utc.with_ymd_and_hms(2009, 1, 1, 12, 17, 30).unwrap();
+        let received_at = sent_at + Duration::minutes(5);
+        assert_eq!(clock_skew(Some(&date), sent_at, received_at), None);
+        assert_eq!(clock_skew(Some(&date), sent_at, sent_at), Some(150));
+    }
+
+    #[test]
+    fn test_compensate_clock_skew() {
+        let date = HeaderValue::from_static("Thu, 01 Jan 2009 12:20:00 GMT");
+        let sent_at = Utc.with_ymd_and_hms(2009, 1, 1, 12, 0, 0).unwrap();
+        let received_at = sent_at + Duration::seconds(2);
+        let offset = Cell::new(0);
+
+        compensate_clock_skew(&offset, Some(&date), sent_at, received_at, 200).unwrap();
+        assert_eq!(offset.get(), 1199);
+
+        let offset = Cell::new(0);
+        match compensate_clock_skew(&offset, Some(&date), sent_at, received_at, 401) {
+            Err(ChefError::ClockSkewError(1199)) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        assert_eq!(offset.get(), 1199);
+
+        let offset = Cell::new(30);
+        compensate_clock_skew(&offset, None, sent_at, received_at, 401).unwrap();
+        assert_eq!(offset.get(), 30);
+    }
 
     #[test]
     fn test_squeeze_path() {
@@ -5,7 +5,11 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 
+extern crate base64;
 extern crate chef_api;
 extern crate failure;
+#[macro_use]
+extern crate log;
+extern crate openssl;
 
 pub mod models;
@@ -0,0 +1,331 @@
+model_use!();
+
+use chef_api::api_client::{ApiClient, Execute};
+use serde::Serialize;
+
+/// A single access control entry, listing who holds a permission.
+///
+/// Servers that support granular ACLs report users and clients separately; older servers only
+/// report a combined list of actors.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Ace {
+    pub actors: Vec<String>,
+    pub groups: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clients: Option<Vec<String>>,
+}
+
+model_impl!(Ace);
+
+impl Ace {
+    fn members_mut(&mut self, principal: &Principal) -> &mut Vec<String> {
+        match *principal {
+            Principal::User(_) => self.users.as_mut().unwrap_or(&mut self.actors),
+            Principal::Client(_) => self.clients.as_mut().unwrap_or(&mut self.actors),
+            Principal::Group(_) => &mut self.groups,
+        }
+    }
+
+    /// Returns true if the principal is granted this permission directly.
+    pub fn contains(&self, principal: &Principal) -> bool {
+        let name = principal.name();
+        let listed = |list: &Vec<String>| list.iter().any(|m| m == name);
+        match *principal {
+            Principal::User(_) => listed(&self.actors) || self.users.iter().any(listed),
+            Principal::Client(_) => listed(&self.actors) || self.clients.iter().any(listed),
+            Principal::Group(_) => listed(&self.groups),
+        }
+    }
+
+    /// Grants this permission to the principal. Returns false if it was already present.
+    pub fn add(&mut self, principal: &Principal) -> bool {
+        if self.contains(principal) {
+            return false;
+        }
+        self.members_mut(principal).push(principal.name().into());
+        true
+    }
+
+    /// Revokes this permission from the principal. Returns false if it was not present.
+    pub fn remove(&mut self, principal: &Principal) -> bool {
+        if !self.contains(principal) {
+            return false;
+        }
+        let name = principal.name();
+        match *principal {
+            Principal::User(_) => {
+                if let Some(ref mut users) = self.users {
+                    users.retain(|u| u != name);
+                }
+                self.actors.retain(|a| a != name);
+            }
+            Principal::Client(_) => {
+                if let Some(ref mut clients) = self.clients {
+                    clients.retain(|c| c != name);
+                }
+                self.actors.retain(|a| a != name);
+            }
+            Principal::Group(_) => self.groups.retain(|g| g != name),
+        }
+        true
+    }
+}
+
+/// The access control list of a Chef object.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Acl {
+    pub create: Ace,
+    pub read: Ace,
+    pub update: Ace,
+    pub delete: Ace,
+    pub grant: Ace,
+}
+
+model_impl!(Acl);
+
+impl Acl {
+    /// Returns the entry for the given permission.
+    pub fn ace(&self, permission: Permission) -> &Ace {
+        match permission {
+            Permission::Create => &self.create,
+            Permission::Read => &self.read,
+            Permission::Update => &self.update,
+            Permission::Delete => &self.delete,
+            Permission::Grant => &self.grant,
+        }
+    }
+
+    /// Returns a mutable reference to the entry for the given permission.
+    pub fn ace_mut(&mut self, permission: Permission) -> &mut Ace {
+        match permission {
+            Permission::Create => &mut self.create,
+            Permission::Read => &mut self.read,
+            Permission::Update => &mut self.update,
+            Permission::Delete => &mut self.delete,
+            Permission::Grant => &mut self.grant,
+        }
+    }
+}
+
+/// The permissions that can be granted on a Chef object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    Create,
+    Read,
+    Update,
+    Delete,
+    Grant,
+}
+
+impl Permission {
+    /// All permissions, in the order the Chef Server lists them.
+    pub const ALL: [Permission; 5] = [
+        Permission::Create,
+        Permission::Read,
+        Permission::Update,
+        Permission::Delete,
+        Permission::Grant,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Permission::Create => "create",
+            Permission::Read => "read",
+            Permission::Update => "update",
+            Permission::Delete => "delete",
+            Permission::Grant => "grant",
+        }
+    }
+}
+
+/// An actor or group that can be granted permissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Principal<'a> {
+    User(&'a str),
+    Client(&'a str),
+    Group(&'a str),
+}
+
+impl<'a> Principal<'a> {
+    pub fn name(&self) -> &'a str {
+        match *self {
+            Principal::User(name) | Principal::Client(name) | Principal::Group(name) => name,
+        }
+    }
+}
+
+/// A Chef object that has an access control list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclObject<'a> {
+    Client(&'a str),
+    Container(&'a str),
+    Cookbook(&'a str),
+    DataBag(&'a str),
+    Environment(&'a str),
+    Group(&'a str),
+    Node(&'a str),
+    Policy(&'a str),
+    Role(&'a str),
+}
+
+macro_rules! acl_request {
+    ($api:expr, $query:ident, $path:ident, $name:expr, $perm:expr, $body:expr, $method:expr) => {{
+        let mut req = $api.$query();
+        req.$path($name).acl();
+        match $perm {
+            Some(perm) => req.permission(perm),
+            None => req.granular(),
+        };
+        req.execute($body, $method)
+    }};
+}
+
+impl<'a> AclObject<'a> {
+    fn execute<B>(
+        &self,
+        api: &ApiClient,
+        permission: Option<Permission>,
+        body: Option<B>,
+        method: &str,
+    ) -> Result<Value, Error>
+    where
+        B: Serialize,
+    {
+        let perm = permission.map(Permission::as_str);
+        match *self {
+            AclObject::Client(n) => acl_request!(api, clients, client, n, perm, body, method),
+            AclObject::Container(n) => {
+                acl_request!(api, containers, container, n, perm, body, method)
+            }
+            AclObject::Cookbook(n) => acl_request!(api, cookbooks, cookbook, n, perm, body, method),
+            AclObject::DataBag(n) => acl_request!(api, data, data_bag, n, perm, body, method),
+            AclObject::Environment(n) => {
+                acl_request!(api, environments, environment, n, perm, body, method)
+            }
+            AclObject::Group(n) => acl_request!(api, groups, group, n, perm, body, method),
+            AclObject::Node(n) => acl_request!(api, nodes, node, n, perm, body, method),
+            AclObject::Policy(n) => acl_request!(api, policies, policy, n, perm, body, method),
+            AclObject::Role(n) => acl_request!(api, roles, role, n, perm, body, method),
+        }
+    }
+}
+
+/// Fetches the access control list of an object.
+pub fn get_acl(api: &ApiClient, object: AclObject) -> Result<Acl, Error> {
+    object
+        .execute(api, None, None::<Value>, "get")
+        .and_then(Acl::try_from)
+}
+
+/// Replaces a single permission on an object with the given entry.
+pub fn update_ace(
+    api: &ApiClient,
+    object: AclObject,
+    permission: Permission,
+    ace: &Ace,
+) -> Result<(), Error> {
+    let mut body = HashMap::new();
+    body.insert(permission.as_str(), ace);
+    object
+        .execute(api, Some(permission), Some(&body), "put")
+        .map(|_| ())
+}
+
+/// Grants `principal` the given permissions on an object.
+///
+/// The current ACL is read first, and only the permissions that actually change are written
+/// back.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// add_to_acl(
+///     &api,
+///     AclObject::Node("web01"),
+///     Principal::Client("deploy"),
+///     &[Permission::Read, Permission::Update],
+/// )
+/// .unwrap();
+/// ```
+pub fn add_to_acl(
+    api: &ApiClient,
+    object: AclObject,
+    principal: Principal,
+    permissions: &[Permission],
+) -> Result<(), Error> {
+    edit_acl(api, object, permissions, |ace| ace.add(&principal))
+}
+
+/// Revokes the given permissions on an object from `principal`.
+pub fn remove_from_acl(
+    api: &ApiClient,
+    object: AclObject,
+    principal: Principal,
+    permissions: &[Permission],
+) -> Result<(), Error> {
+    edit_acl(api, object, permissions, |ace| ace.remove(&principal))
+}
+
+fn edit_acl<F>(
+    api: &ApiClient,
+    object: AclObject,
+    permissions: &[Permission],
+    edit: F,
+) -> Result<(), Error>
+where
+    F: Fn(&mut Ace) -> bool,
+{
+    let mut acl = get_acl(api, object)?;
+    for permission in permissions {
+        let ace = acl.ace_mut(*permission);
+        if edit(ace) {
+            update_ace(api, object, *permission, ace)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_to_legacy_ace() {
+        let mut ace: Ace =
+            serde_json::from_str(r#"{"actors": ["pivotal"], "groups": ["admins"]}"#).unwrap();
+        assert!(ace.add(&Principal::User("barney")));
+        assert!(!ace.add(&Principal::User("barney")));
+        assert!(ace.add(&Principal::Group("users")));
+        assert_eq!(ace.actors, vec!["pivotal", "barney"]);
+        assert_eq!(ace.groups, vec!["admins", "users"]);
+        assert!(ace.users.is_none());
+    }
+
+    #[test]
+    fn test_add_to_granular_ace() {
+        let mut ace: Ace = serde_json::from_str(
+            r#"{"actors": [], "groups": [], "users": ["pivotal"], "clients": []}"#,
+        )
+        .unwrap();
+        assert!(ace.add(&Principal::Client("web01")));
+        assert!(ace.actors.is_empty());
+        assert_eq!(ace.clients.unwrap(), vec!["web01"]);
+    }
+
+    #[test]
+    fn test_remove_from_ace() {
+        let mut ace: Ace = serde_json::from_str(
+            r#"{"actors": ["barney"], "groups": ["admins"], "users": ["barney"]}"#,
+        )
+        .unwrap();
+        assert!(ace.remove(&Principal::User("barney")));
+        assert!(!ace.remove(&Principal::Client("barney")));
+        assert!(ace.actors.is_empty());
+        assert!(ace.users.unwrap().is_empty());
+    }
+}
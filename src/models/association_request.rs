@@ -0,0 +1,162 @@
+model_use!();
+
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+
+/// A pending invitation for a user to join an organization.
+///
+/// Invitations listed for an organization carry the `username`, and those listed for a user carry
+/// the `orgname`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct AssociationRequest {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orgname: Option<String>,
+}
+
+model_impl!(AssociationRequest);
+
+/// A user's answer to an invitation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InviteResponse {
+    Accept,
+    Reject,
+}
+
+impl InviteResponse {
+    fn as_str(self) -> &'static str {
+        match self {
+            InviteResponse::Accept => "accept",
+            InviteResponse::Reject => "reject",
+        }
+    }
+}
+
+fn decode_requests(val: Value) -> Result<Vec<AssociationRequest>, Error> {
+    serde_json::from_value(val).map_err(|e| e.into())
+}
+
+/// Invites a user to join an organization, returning the new invitation.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let invite = invite_user(&api, "bedrock", "barney").unwrap();
+/// ```
+pub fn invite_user(api: &ApiClient, org: &str, user: &str) -> Result<AssociationRequest, Error> {
+    let mut body = HashMap::new();
+    body.insert("user", user);
+    let resp = api
+        .server_organizations()
+        .organization(org)
+        .association_requests()
+        .post(&body)?;
+
+    Ok(AssociationRequest {
+        id: invite_id(&resp)?,
+        username: Some(user.into()),
+        orgname: Some(org.into()),
+    })
+}
+
+/// Extracts the id of a new invitation from the last segment of the `uri` in the response.
+fn invite_id(resp: &Value) -> Result<String, Error> {
+    resp.get("uri")
+        .and_then(Value::as_str)
+        .and_then(|uri| uri.trim_end_matches('/').rsplit('/').next())
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+        .ok_or_else(|| ChefError::KeyMissingError(String::from("uri")).into())
+}
+
+/// Lists the pending invitations sent by an organization.
+pub fn list_organization_invites(
+    api: &ApiClient,
+    org: &str,
+) -> Result<Vec<AssociationRequest>, Error> {
+    api.server_organizations()
+        .organization(org)
+        .association_requests()
+        .get()
+        .and_then(decode_requests)
+}
+
+/// Withdraws a pending invitation sent by an organization.
+pub fn cancel_invite(api: &ApiClient, org: &str, id: &str) -> Result<(), Error> {
+    api.server_organizations()
+        .organization(org)
+        .association_requests()
+        .association_request(id)
+        .delete()
+        .map(|_| ())
+}
+
+/// Lists the pending invitations a user has received.
+pub fn list_user_invites(api: &ApiClient, user: &str) -> Result<Vec<AssociationRequest>, Error> {
+    api.server_users()
+        .user(user)
+        .association_requests()
+        .get()
+        .and_then(decode_requests)
+}
+
+/// Returns the number of pending invitations a user has received.
+pub fn count_user_invites(api: &ApiClient, user: &str) -> Result<u64, Error> {
+    let resp = api
+        .server_users()
+        .user(user)
+        .association_requests()
+        .count()
+        .get()?;
+    Ok(resp.get("value").and_then(Value::as_u64).unwrap_or(0))
+}
+
+/// Accepts or rejects an invitation on behalf of the user who received it.
+pub fn respond_to_invite(
+    api: &ApiClient,
+    user: &str,
+    id: &str,
+    response: InviteResponse,
+) -> Result<(), Error> {
+    let mut body = HashMap::new();
+    body.insert("response", response.as_str());
+    api.server_users()
+        .user(user)
+        .association_requests()
+        .association_request(id)
+        .put(&body)
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_invite_id() {
+        let resp = json!({
+            "uri": "https://chef.example/organizations/bedrock/association_requests/1a2b3c"
+        });
+        assert_eq!(invite_id(&resp).unwrap(), "1a2b3c");
+        assert!(invite_id(&json!({})).is_err());
+        assert!(invite_id(&json!({"uri": ""})).is_err());
+    }
+
+    #[test]
+    fn test_decode_requests() {
+        let requests = decode_requests(json!([
+            {"id": "1a2b3c", "username": "barney"},
+            {"id": "4d5e6f", "orgname": "bedrock"}
+        ]))
+        .unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].username.as_deref(), Some("barney"));
+        assert_eq!(requests[1].orgname.as_deref(), Some("bedrock"));
+        assert!(decode_requests(json!({"id": "1a2b3c"})).is_err());
+    }
+}
@@ -0,0 +1,383 @@
+//! Helpers for working with Chef node attributes.
+
+use chef_api::errors::ChefError;
+use failure::Error;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// A path to a nested attribute: either a dotted string such as `"network.interfaces.eth0"`, or a
+/// list of keys for when a key itself contains a dot. Numeric keys index into arrays.
+pub trait AttributePath {
+    fn keys(&self) -> Vec<&str>;
+
+    fn to_path_string(&self) -> String {
+        self.keys().join(".")
+    }
+}
+
+impl AttributePath for &str {
+    fn keys(&self) -> Vec<&str> {
+        self.split('.').filter(|k| !k.is_empty()).collect()
+    }
+}
+
+impl AttributePath for &String {
+    fn keys(&self) -> Vec<&str> {
+        self.split('.').filter(|k| !k.is_empty()).collect()
+    }
+}
+
+impl AttributePath for &[&str] {
+    fn keys(&self) -> Vec<&str> {
+        self.to_vec()
+    }
+}
+
+impl<const N: usize> AttributePath for [&str; N] {
+    fn keys(&self) -> Vec<&str> {
+        self.to_vec()
+    }
+}
+
+impl AttributePath for Vec<&str> {
+    fn keys(&self) -> Vec<&str> {
+        self.clone()
+    }
+}
+
+/// Path based access to a map of attributes, such as `Node::automatic` or
+/// `Role::default_attributes`.
+///
+/// ```rust
+/// use chef::models::attributes::Attributes;
+/// # use std::collections::HashMap;
+/// # use serde_json::json;
+/// let mut attrs = HashMap::new();
+/// attrs.set_attr("nginx.worker_processes", json!(4)).unwrap();
+/// assert_eq!(attrs.attr("nginx.worker_processes"), Some(&json!(4)));
+/// let workers: u32 = attrs.attr_as(["nginx", "worker_processes"]).unwrap();
+/// assert_eq!(workers, 4);
+/// ```
+pub trait Attributes {
+    /// Returns the value at `path`, if there is one.
+    fn attr<P: AttributePath>(&self, path: P) -> Option<&Value>;
+
+    /// Deserializes the value at `path` into a `T`.
+    fn attr_as<T, P>(&self, path: P) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+        P: AttributePath,
+    {
+        let value = self
+            .attr(&path)
+            .ok_or_else(|| ChefError::KeyMissingError(path.to_path_string()))?;
+        T::deserialize(value).map_err(|e| e.into())
+    }
+
+    /// Sets the value at `path`, creating any missing intermediate hashes. Numeric keys index
+    /// into existing arrays, and an index one past the end appends to the array. If a key on the
+    /// path can't be followed, because the value there is not a hash or the index is out of
+    /// range, an `AttributePathError` is returned and nothing is changed.
+    fn set_attr<P: AttributePath>(&mut self, path: P, value: Value) -> Result<(), Error>;
+
+    /// Removes the value at `path`, returning it.
+    fn delete_attr<P: AttributePath>(&mut self, path: P) -> Option<Value>;
+}
+
+impl<P: AttributePath> AttributePath for &P {
+    fn keys(&self) -> Vec<&str> {
+        (*self).keys()
+    }
+}
+
+fn child<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match *value {
+        Value::Object(ref map) => map.get(key),
+        Value::Array(ref list) => key.parse::<usize>().ok().and_then(|i| list.get(i)),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match *value {
+        Value::Object(ref mut map) => map.get_mut(key),
+        Value::Array(ref mut list) => key.parse::<usize>().ok().and_then(move |i| list.get_mut(i)),
+        _ => None,
+    }
+}
+
+/// Sets `value` at `keys` below `target`, returning a description of the problem if a key can't
+/// be followed. Values are only created below missing keys, so nothing is changed on failure.
+fn set_value(target: &mut Value, keys: &[&str], value: Value) -> Result<(), String> {
+    let (key, rest) = match keys.split_first() {
+        None => {
+            *target = value;
+            return Ok(());
+        }
+        Some(split) => split,
+    };
+    if target.is_null() {
+        *target = Value::Object(Map::new());
+    }
+    match *target {
+        Value::Object(ref mut map) => {
+            set_value(map.entry(*key).or_insert(Value::Null), rest, value)
+        }
+        Value::Array(ref mut list) => match key.parse::<usize>() {
+            Ok(i) if i < list.len() => set_value(&mut list[i], rest, value),
+            Ok(i) if i == list.len() => {
+                list.push(Value::Null);
+                set_value(&mut list[i], rest, value)
+            }
+            Ok(i) => Err(format!(
+                "index {} is out of range for an array of {}",
+                i,
+                list.len()
+            )),
+            Err(_) => Err(format!("{} is not an index into an array", key)),
+        },
+        ref other => Err(format!("{} is not a hash or an array", other)),
+    }
+}
+
+impl Attributes for HashMap<String, Value> {
+    fn attr<P: AttributePath>(&self, path: P) -> Option<&Value> {
+        let keys = path.keys();
+        let (first, rest) = keys.split_first()?;
+        rest.iter()
+            .try_fold(self.get(*first)?, |value, key| child(value, key))
+    }
+
+    fn set_attr<P: AttributePath>(&mut self, path: P, value: Value) -> Result<(), Error> {
+        let keys = path.keys();
+        let (first, rest) = keys
+            .split_first()
+            .ok_or_else(|| ChefError::AttributePathError(String::new(), "empty path".into()))?;
+        let entry = self.entry((*first).into()).or_insert(Value::Null);
+        set_value(entry, rest, value)
+            .map_err(|e| ChefError::AttributePathError(path.to_path_string(), e).into())
+    }
+
+    fn delete_attr<P: AttributePath>(&mut self, path: P) -> Option<Value> {
+        let keys = path.keys();
+        let (last, parents) = keys.split_last()?;
+        let (first, parents) = match parents.split_first() {
+            Some(split) => split,
+            None => return self.remove(*last),
+        };
+        let mut value = self.get_mut(*first)?;
+        for key in parents {
+            value = child_mut(value, key)?;
+        }
+        match *value {
+            Value::Object(ref mut map) => map.remove(*last),
+            Value::Array(ref mut list) => match last.parse::<usize>() {
+                Ok(i) if i < list.len() => Some(list.remove(i)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Looks up `path` in several precedence levels, from lowest to highest priority, merging the
+/// results as Chef would.
+pub(crate) fn merged_attr<P: AttributePath>(
+    levels: &[&HashMap<String, Value>],
+    path: P,
+) -> Option<Value> {
+    levels
+        .iter()
+        .filter_map(|level| level.attr(&path))
+        .filter(|value| !value.is_null())
+        .fold(None, |merged, value| match merged {
+            None => Some(value.clone()),
+            Some(merged) => Some(hash_only_merge(merged, value)),
+        })
+}
+
+/// Merges `merge_with` onto `merge_onto` the way Chef merges attributes within the default and
+/// override precedence levels: hashes are merged recursively, arrays are combined without
+/// duplicates, and any other value replaces the existing one. A `null` never replaces a value.
+pub fn deep_merge(merge_onto: Value, merge_with: &Value) -> Value {
+    match (merge_onto, merge_with) {
+        (Value::Object(onto), Value::Object(with)) => {
+            Value::Object(merge_maps(onto, with, deep_merge))
+        }
+        (Value::Array(mut onto), Value::Array(with)) => {
+            for item in with {
+                if !onto.contains(item) {
+                    onto.push(item.clone());
+                }
+            }
+            Value::Array(onto)
+        }
+        (onto, Value::Null) => onto,
+        (_, with) => with.clone(),
+    }
+}
+
+/// Merges `merge_with` onto `merge_onto` the way Chef merges attributes across precedence
+/// levels: hashes are merged recursively, and any other value, including an array, replaces the
+/// existing one. A `null` never replaces a value.
+pub fn hash_only_merge(merge_onto: Value, merge_with: &Value) -> Value {
+    match (merge_onto, merge_with) {
+        (Value::Object(onto), Value::Object(with)) => {
+            Value::Object(merge_maps(onto, with, hash_only_merge))
+        }
+        (onto, Value::Null) => onto,
+        (_, with) => with.clone(),
+    }
+}
+
+fn merge_maps<F>(
+    mut onto: Map<String, Value>,
+    with: &Map<String, Value>,
+    merge: F,
+) -> Map<String, Value>
+where
+    F: Fn(Value, &Value) -> Value,
+{
+    for (key, value) in with {
+        let merged = match onto.remove(key) {
+            Some(existing) => merge(existing, value),
+            None => value.clone(),
+        };
+        onto.insert(key.clone(), merged);
+    }
+    onto
+}
+
+pub(crate) fn to_value(attrs: &HashMap<String, Value>) -> Value {
+    Value::Object(attrs.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+}
+
+pub(crate) fn from_value(value: Value) -> HashMap<String, Value> {
+    match value {
+        Value::Object(map) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Combines several components of one precedence level, from lowest to highest priority.
+pub(crate) fn merge_level<'a, I>(components: I) -> Value
+where
+    I: IntoIterator<Item = &'a HashMap<String, Value>>,
+{
+    components
+        .into_iter()
+        .fold(Value::Null, |merged, component| {
+            deep_merge(merged, &to_value(component))
+        })
+}
+
+/// Deserializes a looked up attribute into a `T`.
+pub(crate) fn attr_as<T, P>(value: Option<Value>, path: P) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    P: AttributePath,
+{
+    let value = value.ok_or_else(|| ChefError::KeyMissingError(path.to_path_string()))?;
+    serde_json::from_value(value).map_err(|e| e.into())
+}
+
+/// Removes the value at `path` from several precedence levels, returning the merged value it had.
+pub(crate) fn delete_merged_attr<P: AttributePath>(
+    levels: &mut [&mut HashMap<String, Value>],
+    path: P,
+) -> Option<Value> {
+    levels
+        .iter_mut()
+        .filter_map(|level| level.delete_attr(&path))
+        .filter(|value| !value.is_null())
+        .fold(None, |merged, value| match merged {
+            None => Some(value),
+            Some(merged) => Some(hash_only_merge(merged, &value)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_deep_merge() {
+        let onto = json!({"a": {"b": 1, "c": [1, 2]}, "d": "x"});
+        let with = json!({"a": {"c": [2, 3], "e": true}, "d": null});
+        assert_eq!(
+            deep_merge(onto, &with),
+            json!({"a": {"b": 1, "c": [1, 2, 3], "e": true}, "d": "x"})
+        );
+    }
+
+    #[test]
+    fn test_attribute_paths() {
+        let mut attrs = from_value(json!({
+            "network": {"interfaces": {"eth0": {"addresses": {"10.0.0.1": {"family": "inet"}}}}},
+            "tags": ["web", "prod"]
+        }));
+        assert_eq!(attrs.attr("tags.1"), Some(&json!("prod")));
+        assert_eq!(
+            attrs.attr([
+                "network",
+                "interfaces",
+                "eth0",
+                "addresses",
+                "10.0.0.1",
+                "family"
+            ]),
+            Some(&json!("inet"))
+        );
+        assert!(attrs.attr("network.interfaces.eth1").is_none());
+        assert!(attrs.attr_as::<String, _>("tags").is_err());
+
+        attrs.set_attr("a.b.c", json!(1)).unwrap();
+        assert_eq!(attrs.delete_attr("a.b"), Some(json!({"c": 1})));
+        assert_eq!(attrs.attr("a"), Some(&json!({})));
+        assert_eq!(attrs.delete_attr("missing.key"), None);
+    }
+
+    #[test]
+    fn test_attribute_arrays() {
+        let mut attrs = from_value(json!({
+            "tags": ["web", "prod"],
+            "mounts": [{"path": "/", "opts": ["rw"]}],
+            "port": 80
+        }));
+
+        attrs.set_attr("tags.0", json!("db")).unwrap();
+        attrs.set_attr("tags.2", json!("eu")).unwrap();
+        assert_eq!(attrs.attr("tags"), Some(&json!(["db", "prod", "eu"])));
+        attrs.set_attr("mounts.0.opts.1", json!("noexec")).unwrap();
+        assert_eq!(attrs.attr("mounts.0.opts"), Some(&json!(["rw", "noexec"])));
+
+        for path in &["tags.extra", "tags.5", "port.number", "mounts.0.path.x"] {
+            let err = attrs.set_attr(*path, json!(true)).unwrap_err();
+            match err.downcast_ref::<ChefError>() {
+                Some(ChefError::AttributePathError(p, _)) => assert_eq!(p, path),
+                _ => panic!("unexpected error: {}", err),
+            }
+        }
+        assert_eq!(attrs.attr("tags"), Some(&json!(["db", "prod", "eu"])));
+        assert_eq!(attrs.attr("port"), Some(&json!(80)));
+
+        assert_eq!(attrs.delete_attr("tags.1"), Some(json!("prod")));
+        assert_eq!(attrs.attr("tags"), Some(&json!(["db", "eu"])));
+        assert_eq!(attrs.delete_attr("mounts.0.opts.0"), Some(json!("rw")));
+        assert_eq!(attrs.delete_attr("mounts.0.path"), Some(json!("/")));
+        assert_eq!(attrs.delete_attr("tags.7"), None);
+        assert_eq!(attrs.delete_attr("tags.x"), None);
+    }
+
+    #[test]
+    fn test_hash_only_merge() {
+        let onto = json!({"a": {"b": 1, "c": [1, 2]}});
+        let with = json!({"a": {"c": [3]}});
+        assert_eq!(
+            hash_only_merge(onto, &with),
+            json!({"a": {"b": 1, "c": [3]}})
+        );
+    }
+}
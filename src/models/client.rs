@@ -1,5 +1,11 @@
 model_use!();
 
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+use std::path::Path;
+
+use crate::models::key::{generate_key_pair, write_private_key};
+
 chef_json_type!(ClientJsonClass, "Chef::Client");
 chef_json_type!(ClientChefType, "client");
 
@@ -17,3 +23,148 @@ pub struct Client {
 model_impl!(Client);
 model_list!(ClientList);
 model_result!(Client, ClientResult);
+
+/// Determines where the key pair for a new client is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyGeneration {
+    /// Ask the Chef Server to generate the key pair and return the private key.
+    #[default]
+    Server,
+    /// Generate an RSA key pair with the given number of bits locally, and only send the
+    /// public key to the Chef Server.
+    Local(u32),
+}
+
+/// Options for `create_client`.
+#[derive(Debug, Clone, Default)]
+pub struct ClientCreateOptions {
+    /// Whether the new client is a validator.
+    pub validator: bool,
+    /// How the client's key pair is generated.
+    pub key: KeyGeneration,
+}
+
+#[derive(Debug, Serialize)]
+struct ClientCreateRequest<'a> {
+    name: &'a str,
+    validator: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_key: Option<bool>,
+}
+
+/// A newly created client, along with its private key.
+#[derive(Debug, Clone, Default)]
+pub struct CreatedClient {
+    pub name: String,
+    pub uri: Option<String>,
+    pub public_key: Option<String>,
+    pub private_key: Option<String>,
+}
+
+impl CreatedClient {
+    fn from_response(name: &str, val: &Value) -> Self {
+        let get = |v: &Value, k: &str| v.get(k).and_then(Value::as_str).map(String::from);
+        let key = val.get("chef_key").unwrap_or(val);
+        CreatedClient {
+            name: name.into(),
+            uri: get(val, "uri"),
+            public_key: get(key, "public_key"),
+            private_key: get(key, "private_key"),
+        }
+    }
+
+    /// Writes the client's private key to `path`, readable and writable only by the current user.
+    pub fn write_private_key<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        write_private_key(path.as_ref(), self.private_key.as_ref())
+    }
+}
+
+/// Creates a new client called `name`.
+///
+/// Depending on `opts.key`, either the Chef Server generates the key pair, or one is generated
+/// locally and only the public key is submitted. In both cases the returned `CreatedClient`
+/// holds the private key.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let opts = ClientCreateOptions { key: KeyGeneration::Local(2048), ..Default::default() };
+/// let client = create_client(&api, "my_client", &opts).unwrap();
+/// client.write_private_key("my_client.pem").unwrap();
+/// ```
+pub fn create_client(
+    api: &ApiClient,
+    name: &str,
+    opts: &ClientCreateOptions,
+) -> Result<CreatedClient, Error> {
+    let mut body = ClientCreateRequest {
+        name,
+        validator: opts.validator,
+        public_key: None,
+        create_key: None,
+    };
+
+    let private_key = match opts.key {
+        KeyGeneration::Server => {
+            body.create_key = Some(true);
+            None
+        }
+        KeyGeneration::Local(bits) => {
+            let (public_key, private_key) = generate_key_pair(bits)?;
+            body.public_key = Some(public_key);
+            Some(private_key)
+        }
+    };
+
+    let resp = api.clients().post(&body)?;
+    let mut created = CreatedClient::from_response(name, &resp);
+    if private_key.is_some() {
+        created.public_key = body.public_key;
+        created.private_key = private_key;
+    }
+
+    if created.private_key.is_none() {
+        return Err(ChefError::KeyMissingError(String::from("private_key")).into());
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_created_client_from_response() {
+        let resp = serde_json::json!({
+            "uri": "https://chef.local/organizations/bedrock/clients/barney",
+            "chef_key": {
+                "name": "default",
+                "public_key": "PUBLIC",
+                "private_key": "PRIVATE",
+                "expiration_date": "infinity"
+            }
+        });
+        let created = CreatedClient::from_response("barney", &resp);
+        assert_eq!(created.private_key.unwrap(), "PRIVATE");
+        assert_eq!(created.public_key.unwrap(), "PUBLIC");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_key_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let created = CreatedClient {
+            private_key: Some(String::from("PRIVATE")),
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join("chef_created_client_test.pem");
+        created.write_private_key(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
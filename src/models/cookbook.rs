@@ -1,5 +1,7 @@
 model_use!();
 
+use crate::models::Version;
+
 chef_json_type!(CookbookVersionJsonClass, "Chef::CookbookVersion");
 chef_json_type!(CookbookVersionChefType, "cookbook_version");
 
@@ -7,7 +9,7 @@ chef_json_type!(CookbookVersionChefType, "cookbook_version");
 #[serde(default)]
 pub struct CookbookVersion {
     pub cookbook_name: String,
-    chef_type: String,
+    chef_type: CookbookVersionChefType,
     pub version: String,
     pub attributes: Vec<HashMap<String, Value>>,
     pub metadata: HashMap<String, Value>,
@@ -18,8 +20,15 @@ pub struct CookbookVersion {
     root_files: Vec<HashMap<String, Value>>,
     #[serde(rename = "frozen?")]
     pub frozen: bool,
-    json_class: String,
+    json_class: CookbookVersionJsonClass,
 }
 
 model_impl!(CookbookVersion);
+
+impl CookbookVersion {
+    /// Parses the `version` of this cookbook.
+    pub fn parsed_version(&self) -> Result<Version, Error> {
+        self.version.parse()
+    }
+}
 model_list!(CookbookVersionList);
@@ -1,5 +1,8 @@
 model_use!();
 
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+
 chef_json_type!(DataBagJsonClass, "Chef::DataBag");
 chef_json_type!(DataBagChefType, "data_bag");
 
@@ -14,3 +17,57 @@ pub struct DataBag {
 
 model_impl!(DataBag);
 model_list!(DataBagList);
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_')
+}
+
+/// Checks that `name` only contains characters Chef allows in a data bag name.
+pub fn validate_data_bag_name(name: &str) -> Result<(), Error> {
+    if valid_name(name) {
+        Ok(())
+    } else {
+        Err(ChefError::InvalidDataBagNameError(name.into()).into())
+    }
+}
+
+/// Checks that `id` only contains characters Chef allows in a data bag item id.
+pub fn validate_data_bag_item_id(id: &str) -> Result<(), Error> {
+    if valid_name(id) {
+        Ok(())
+    } else {
+        Err(ChefError::InvalidDataBagItemIdError(id.into()).into())
+    }
+}
+
+/// Lists the names of all data bags.
+pub fn list_data_bags(api: &ApiClient) -> Result<Vec<String>, Error> {
+    let bags: DataBagList = api.data().get()?.into();
+    Ok(bags.collect())
+}
+
+/// Creates a new, empty data bag.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// create_data_bag(&api, "users").unwrap();
+/// ```
+pub fn create_data_bag(api: &ApiClient, name: &str) -> Result<(), Error> {
+    validate_data_bag_name(name)?;
+    let bag = DataBag {
+        name: Some(name.into()),
+        ..Default::default()
+    };
+    api.data().post(&bag).map(|_| ())
+}
+
+/// Deletes a data bag, along with all of its items.
+pub fn delete_data_bag(api: &ApiClient, name: &str) -> Result<(), Error> {
+    validate_data_bag_name(name)?;
+    api.data().data_bag(name).delete().map(|_| ())
+}
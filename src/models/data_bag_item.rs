@@ -1,5 +1,10 @@
 model_use!();
 
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+
+use crate::models::{create_data_bag, validate_data_bag_item_id, validate_data_bag_name};
+
 chef_json_type!(DataBagItemJsonClass, "Chef::DataBagItem");
 chef_json_type!(DataBagItemChefType, "data_bag_item");
 
@@ -15,3 +20,161 @@ pub struct DataBagItem {
 
 model_impl!(DataBagItem);
 model_list!(DataBagItemList);
+
+impl DataBagItem {
+    /// Creates a new item with id `id` in `data_bag`. The `id` key of `raw_data` is filled in if
+    /// missing, and must otherwise match `id`.
+    ///
+    /// ```rust
+    /// # use chef::models::DataBagItem;
+    /// # use std::collections::HashMap;
+    /// let mut data = HashMap::new();
+    /// data.insert(String::from("shell"), "/bin/zsh".into());
+    /// let item = DataBagItem::new("users", "barney", data).unwrap();
+    /// assert_eq!(item.raw_data["id"], "barney");
+    /// ```
+    pub fn new(
+        data_bag: &str,
+        id: &str,
+        mut raw_data: HashMap<String, Value>,
+    ) -> Result<Self, Error> {
+        validate_data_bag_name(data_bag)?;
+        validate_data_bag_item_id(id)?;
+        match raw_data.get("id") {
+            None => {
+                raw_data.insert(String::from("id"), Value::String(id.into()));
+            }
+            Some(data_id) if data_id.as_str() != Some(id) => {
+                return Err(
+                    ChefError::DataBagItemIdMismatchError(id.into(), data_id.to_string()).into(),
+                );
+            }
+            Some(_) => (),
+        }
+        Ok(DataBagItem {
+            id: Some(id.into()),
+            data_bag: Some(data_bag.into()),
+            raw_data,
+            ..Default::default()
+        })
+    }
+
+    /// The id of the item.
+    pub fn id(&self) -> Option<&str> {
+        self.id
+            .as_deref()
+            .or_else(|| self.raw_data.get("id").and_then(Value::as_str))
+    }
+
+    /// The name of the data bag the item belongs to.
+    pub fn data_bag(&self) -> Option<&str> {
+        self.data_bag.as_deref()
+    }
+
+    /// Checks the data bag name and item id, returning both.
+    fn location(&self) -> Result<(&str, &str), Error> {
+        let data_bag = self
+            .data_bag()
+            .ok_or_else(|| ChefError::KeyMissingError(String::from("data_bag")))?;
+        let id = self
+            .id()
+            .ok_or_else(|| ChefError::KeyMissingError(String::from("id")))?;
+        validate_data_bag_name(data_bag)?;
+        validate_data_bag_item_id(id)?;
+
+        match self.raw_data.get("id").and_then(Value::as_str) {
+            Some(data_id) if data_id == id => Ok((data_bag, id)),
+            data_id => Err(ChefError::DataBagItemIdMismatchError(
+                id.into(),
+                data_id.unwrap_or_default().into(),
+            )
+            .into()),
+        }
+    }
+}
+
+/// Lists the ids of the items in a data bag.
+pub fn list_data_bag_items(api: &ApiClient, data_bag: &str) -> Result<Vec<String>, Error> {
+    validate_data_bag_name(data_bag)?;
+    let items: DataBagItemList = api.data().data_bag(data_bag).get()?.into();
+    Ok(items.collect())
+}
+
+/// Fetches a data bag item.
+pub fn get_data_bag_item(api: &ApiClient, data_bag: &str, id: &str) -> Result<DataBagItem, Error> {
+    validate_data_bag_name(data_bag)?;
+    validate_data_bag_item_id(id)?;
+    let data = api.data().data_bag(data_bag).item(id).get()?;
+    DataBagItem::new(data_bag, id, serde_json::from_value(data)?)
+}
+
+/// Creates a new data bag item. The data bag must already exist.
+pub fn create_data_bag_item(api: &ApiClient, item: &DataBagItem) -> Result<(), Error> {
+    let (data_bag, _) = item.location()?;
+    api.data()
+        .data_bag(data_bag)
+        .post(&item.raw_data)
+        .map(|_| ())
+}
+
+/// Replaces the contents of an existing data bag item.
+pub fn update_data_bag_item(api: &ApiClient, item: &DataBagItem) -> Result<(), Error> {
+    let (data_bag, id) = item.location()?;
+    api.data()
+        .data_bag(data_bag)
+        .item(id)
+        .put(&item.raw_data)
+        .map(|_| ())
+}
+
+/// Creates or replaces a data bag item, creating the data bag as well if needed.
+pub fn save_data_bag_item(api: &ApiClient, item: &DataBagItem) -> Result<(), Error> {
+    let (data_bag, _) = item.location()?;
+    match update_data_bag_item(api, item) {
+        Err(e) if is_status(&e, 404) => {
+            match create_data_bag(api, data_bag) {
+                Err(e) if !is_status(&e, 409) => return Err(e),
+                _ => (),
+            }
+            create_data_bag_item(api, item)
+        }
+        res => res,
+    }
+}
+
+/// Deletes a data bag item.
+pub fn delete_data_bag_item(api: &ApiClient, data_bag: &str, id: &str) -> Result<(), Error> {
+    validate_data_bag_name(data_bag)?;
+    validate_data_bag_item_id(id)?;
+    api.data().data_bag(data_bag).item(id).delete().map(|_| ())
+}
+
+/// Whether `err` is the Chef Server responding with `status`.
+pub(crate) fn is_status(err: &Error, status: u16) -> bool {
+    match err.downcast_ref::<ChefError>() {
+        Some(ChefError::ChefServerResponseError(s)) => *s == status,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::validate_data_bag_name;
+
+    #[test]
+    fn test_validate_names() {
+        assert!(validate_data_bag_name("app-secrets_v1.2").is_ok());
+        assert!(validate_data_bag_name("").is_err());
+        assert!(validate_data_bag_name("bad name").is_err());
+        assert!(validate_data_bag_item_id("../etc").is_err());
+    }
+
+    #[test]
+    fn test_mismatched_id() {
+        let mut data = HashMap::new();
+        data.insert(String::from("id"), Value::String(String::from("fred")));
+        assert!(DataBagItem::new("users", "barney", data.clone()).is_err());
+        assert!(DataBagItem::new("users", "fred", data).is_ok());
+    }
+}
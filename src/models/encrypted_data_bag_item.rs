@@ -0,0 +1,339 @@
+use base64::{engine::general_purpose, Engine as _};
+use chef_api::errors::ChefError;
+use failure::Error;
+use openssl::hash::{hash, MessageDigest};
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::rand::rand_bytes;
+use openssl::sign::Signer;
+use openssl::symm::{decrypt, decrypt_aead, encrypt, encrypt_aead, Cipher};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::models::DataBagItem;
+
+const CBC_CIPHER: &str = "aes-256-cbc";
+const GCM_CIPHER: &str = "aes-256-gcm";
+
+/// The shared secret used to encrypt and decrypt data bag items.
+#[derive(Clone, PartialEq)]
+pub struct DataBagSecret(Vec<u8>);
+
+impl fmt::Debug for DataBagSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("DataBagSecret(..)")
+    }
+}
+
+impl DataBagSecret {
+    /// Uses the given bytes as the secret, as is.
+    pub fn new<S: AsRef<[u8]>>(secret: S) -> Self {
+        DataBagSecret(secret.as_ref().into())
+    }
+
+    /// Reads the secret from a file, such as `/etc/chef/encrypted_data_bag_secret`. Like Chef,
+    /// leading and trailing whitespace is ignored.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut secret = String::new();
+        File::open(path)?.read_to_string(&mut secret)?;
+        Ok(DataBagSecret::new(secret.trim()))
+    }
+
+    /// Generates a random secret of `len` bytes.
+    pub fn generate(len: usize) -> Result<Self, Error> {
+        let mut secret = vec![0; len];
+        rand_bytes(&mut secret)?;
+        Ok(DataBagSecret(secret))
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn key(&self) -> Result<Vec<u8>, Error> {
+        Ok(hash(MessageDigest::sha256(), &self.0)?.to_vec())
+    }
+
+    fn hmac(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let key = PKey::hmac(&self.0)?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+        signer.update(data)?;
+        Ok(signer.sign_to_vec()?)
+    }
+}
+
+/// The formats Chef uses for encrypted data bag items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncryptionVersion {
+    /// AES-256-CBC.
+    V1,
+    /// AES-256-CBC, with an HMAC of the encrypted data.
+    V2,
+    /// AES-256-GCM.
+    #[default]
+    V3,
+}
+
+/// A single encrypted value within an encrypted data bag item.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EncryptedValue {
+    pub encrypted_data: String,
+    pub iv: String,
+    pub version: u8,
+    pub cipher: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmac: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_tag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonWrapper {
+    json_wrapper: Value,
+}
+
+fn encode64(data: &[u8]) -> String {
+    general_purpose::STANDARD.encode(data)
+}
+
+/// Chef wraps base64 values at 60 characters, so ignore any whitespace.
+fn decode64(data: &str) -> Result<Vec<u8>, Error> {
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| ChefError::DecryptionError(e.to_string()).into())
+}
+
+impl EncryptedValue {
+    /// Encrypts a single value.
+    pub fn encrypt(
+        value: &Value,
+        secret: &DataBagSecret,
+        version: EncryptionVersion,
+    ) -> Result<Self, Error> {
+        let key = secret.key()?;
+        let plaintext = serde_json::to_vec(&JsonWrapper {
+            json_wrapper: value.clone(),
+        })?;
+
+        match version {
+            EncryptionVersion::V1 | EncryptionVersion::V2 => {
+                let mut iv = [0; 16];
+                rand_bytes(&mut iv)?;
+                let data = encrypt(Cipher::aes_256_cbc(), &key, Some(&iv), &plaintext)?;
+                let encrypted_data = encode64(&data);
+                let (version, hmac) = if version == EncryptionVersion::V2 {
+                    (2, Some(encode64(&secret.hmac(encrypted_data.as_bytes())?)))
+                } else {
+                    (1, None)
+                };
+                Ok(EncryptedValue {
+                    encrypted_data,
+                    iv: encode64(&iv),
+                    version,
+                    cipher: CBC_CIPHER.into(),
+                    hmac,
+                    auth_tag: None,
+                })
+            }
+            EncryptionVersion::V3 => {
+                let mut iv = [0; 12];
+                rand_bytes(&mut iv)?;
+                let mut tag = [0; 16];
+                let data = encrypt_aead(
+                    Cipher::aes_256_gcm(),
+                    &key,
+                    Some(&iv),
+                    &[],
+                    &plaintext,
+                    &mut tag,
+                )?;
+                Ok(EncryptedValue {
+                    encrypted_data: encode64(&data),
+                    iv: encode64(&iv),
+                    version: 3,
+                    cipher: GCM_CIPHER.into(),
+                    hmac: None,
+                    auth_tag: Some(encode64(&tag)),
+                })
+            }
+        }
+    }
+
+    /// Decrypts a single value, verifying its HMAC or authentication tag where the format has one.
+    pub fn decrypt(&self, secret: &DataBagSecret) -> Result<Value, Error> {
+        let key = secret.key()?;
+        let iv = decode64(&self.iv)?;
+        let data = decode64(&self.encrypted_data)?;
+
+        let plaintext = match (self.version, self.cipher.as_str()) {
+            (1, CBC_CIPHER) | (2, CBC_CIPHER) => {
+                if self.version == 2 {
+                    let expected = secret.hmac(self.encrypted_data.as_bytes())?;
+                    let candidate =
+                        decode64(self.hmac.as_ref().ok_or(ChefError::HmacMismatchError)?)?;
+                    if candidate.len() != expected.len() || !memcmp::eq(&candidate, &expected) {
+                        return Err(ChefError::HmacMismatchError.into());
+                    }
+                }
+                decrypt(Cipher::aes_256_cbc(), &key, Some(&iv), &data).map_err(|_| {
+                    ChefError::DecryptionError(String::from(
+                        "bad decrypt; the secret is most likely incorrect",
+                    ))
+                })?
+            }
+            (3, GCM_CIPHER) => {
+                let tag = decode64(
+                    self.auth_tag
+                        .as_ref()
+                        .ok_or(ChefError::AuthTagMismatchError)?,
+                )?;
+                decrypt_aead(Cipher::aes_256_gcm(), &key, Some(&iv), &[], &data, &tag)
+                    .map_err(|_| ChefError::AuthTagMismatchError)?
+            }
+            (version, cipher) => {
+                return Err(ChefError::DecryptionError(format!(
+                    "unsupported format version {} with cipher {}",
+                    version, cipher
+                ))
+                .into())
+            }
+        };
+
+        serde_json::from_slice::<JsonWrapper>(&plaintext)
+            .map(|w| w.json_wrapper)
+            .map_err(|_| {
+                ChefError::DecryptionError(String::from(
+                    "decrypted data is not valid JSON; the secret is most likely incorrect",
+                ))
+                .into()
+            })
+    }
+}
+
+/// Decrypts every value of an encrypted data bag item, except for its `id`.
+pub fn decrypt_item_data(
+    data: &HashMap<String, Value>,
+    secret: &DataBagSecret,
+) -> Result<HashMap<String, Value>, Error> {
+    let mut output = HashMap::new();
+    for (key, value) in data {
+        if key == "id" {
+            output.insert(key.clone(), value.clone());
+            continue;
+        }
+        let encrypted: EncryptedValue = serde_json::from_value(value.clone()).map_err(|_| {
+            ChefError::DecryptionError(format!("the value of {} is not encrypted", key))
+        })?;
+        output.insert(key.clone(), encrypted.decrypt(secret)?);
+    }
+    Ok(output)
+}
+
+/// Encrypts every value of a data bag item, except for its `id`.
+pub fn encrypt_item_data(
+    data: &HashMap<String, Value>,
+    secret: &DataBagSecret,
+    version: EncryptionVersion,
+) -> Result<HashMap<String, Value>, Error> {
+    let mut output = HashMap::new();
+    for (key, value) in data {
+        let value = if key == "id" {
+            value.clone()
+        } else {
+            serde_json::to_value(EncryptedValue::encrypt(value, secret, version)?)?
+        };
+        output.insert(key.clone(), value);
+    }
+    Ok(output)
+}
+
+impl DataBagItem {
+    /// Returns a copy of this item with its values decrypted.
+    ///
+    /// ```rust,no_run
+    /// # use chef_api::api_client::ApiClient;
+    /// # use chef::models::*;
+    /// # let api = ApiClient::from_credentials(None).unwrap();
+    /// let item = get_data_bag_item(&api, "passwords", "mysql").unwrap();
+    /// let secret = DataBagSecret::from_file("/etc/chef/encrypted_data_bag_secret").unwrap();
+    /// let decrypted = item.decrypt(&secret).unwrap();
+    /// println!("{:?}", decrypted.raw_data.get("password"));
+    /// ```
+    pub fn decrypt(&self, secret: &DataBagSecret) -> Result<DataBagItem, Error> {
+        let mut item = self.clone();
+        item.raw_data = decrypt_item_data(&self.raw_data, secret)?;
+        Ok(item)
+    }
+
+    /// Returns a copy of this item with its values encrypted.
+    pub fn encrypt(
+        &self,
+        secret: &DataBagSecret,
+        version: EncryptionVersion,
+    ) -> Result<DataBagItem, Error> {
+        let mut item = self.clone();
+        item.raw_data = encrypt_item_data(&self.raw_data, secret, version)?;
+        Ok(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ITEM: &str = "fixtures/encrypted_data_bag_item.json";
+    const SECRET: &str = "fixtures/encrypted_data_bag_secret";
+
+    fn fixture() -> HashMap<String, Value> {
+        serde_json::from_reader(File::open(ITEM).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_decrypt_chef_item() {
+        let secret = DataBagSecret::from_file(SECRET).unwrap();
+        let item = decrypt_item_data(&fixture(), &secret).unwrap();
+        assert_eq!(item["id"], json!("passwords"));
+        assert_eq!(item["mysql"], json!("hunter2"));
+        assert_eq!(item["postgres"], json!({"user": "deploy", "port": 5432}));
+        assert_eq!(item["redis"], json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let secret = DataBagSecret::new("bedrock");
+        let value = json!({"user": "fred"});
+        for version in &[
+            EncryptionVersion::V1,
+            EncryptionVersion::V2,
+            EncryptionVersion::V3,
+        ] {
+            let encrypted = EncryptedValue::encrypt(&value, &secret, *version).unwrap();
+            assert_eq!(encrypted.decrypt(&secret).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_wrong_secret() {
+        let secret = DataBagSecret::new("not the secret");
+        let item = fixture();
+        let postgres: EncryptedValue = serde_json::from_value(item["postgres"].clone()).unwrap();
+        let err = postgres.decrypt(&secret).unwrap_err();
+        assert!(matches!(
+            err.downcast::<ChefError>().unwrap(),
+            ChefError::HmacMismatchError
+        ));
+
+        let redis: EncryptedValue = serde_json::from_value(item["redis"].clone()).unwrap();
+        let err = redis.decrypt(&secret).unwrap_err();
+        assert!(matches!(
+            err.downcast::<ChefError>().unwrap(),
+            ChefError::AuthTagMismatchError
+        ));
+    }
+}
@@ -1,4 +1,12 @@
 model_use!();
+
+use crate::models::attributes::{self, delete_merged_attr, merged_attr, AttributePath, Attributes};
+use crate::models::{CookbookVersion, RunListItem, VersionConstraint};
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
 chef_json_type!(EnvironmentJsonClass, "Chef::Environment");
 chef_json_type!(EnvironmentChefType, "environment");
 
@@ -16,3 +24,199 @@ pub struct Environment {
 
 model_impl!(Environment);
 model_list!(EnvironmentList);
+
+impl Environment {
+    /// Parses the `cookbook_versions` pins of this environment, failing on the first invalid
+    /// constraint.
+    pub fn cookbook_constraints(&self) -> Result<HashMap<String, VersionConstraint>, Error> {
+        self.cookbook_versions
+            .iter()
+            .map(|(name, constraint)| Ok((name.clone(), constraint.parse()?)))
+            .collect()
+    }
+
+    /// Returns the value of the attribute at `path`, with override attributes taking precedence
+    /// over default attributes.
+    pub fn attr<P: AttributePath>(&self, path: P) -> Option<Value> {
+        merged_attr(&[&self.default_attributes, &self.override_attributes], path)
+    }
+
+    /// Deserializes the value of the attribute at `path` into a `T`.
+    pub fn attr_as<T, P>(&self, path: P) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+        P: AttributePath,
+    {
+        attributes::attr_as(self.attr(&path), path)
+    }
+
+    /// Sets a default attribute at `path`, as `Attributes::set_attr` does.
+    pub fn set_default_attr<P: AttributePath>(
+        &mut self,
+        path: P,
+        value: Value,
+    ) -> Result<(), Error> {
+        self.default_attributes.set_attr(path, value)
+    }
+
+    /// Sets an override attribute at `path`, as `Attributes::set_attr` does.
+    pub fn set_override_attr<P: AttributePath>(
+        &mut self,
+        path: P,
+        value: Value,
+    ) -> Result<(), Error> {
+        self.override_attributes.set_attr(path, value)
+    }
+
+    /// Removes the attribute at `path` from both the default and override attributes, returning
+    /// its merged value.
+    pub fn delete_attr<P: AttributePath>(&mut self, path: P) -> Option<Value> {
+        delete_merged_attr(
+            &mut [&mut self.default_attributes, &mut self.override_attributes],
+            path,
+        )
+    }
+}
+
+/// Asks the Chef Server to solve the cookbook dependencies of `run_list`, subject to the
+/// `cookbook_versions` constraints of `environment`.
+///
+/// Returns the solved cookbook versions, sorted by cookbook name. If the constraints cannot be
+/// satisfied, a `ChefError::DepsolverError` describing the problem is returned.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let run_list = parse_run_list(&["role[web]", "recipe[monitoring]"]).unwrap();
+/// for cookbook in resolve_run_list(&api, "production", &run_list).unwrap() {
+///     println!("{} {}", cookbook.cookbook_name, cookbook.version);
+/// }
+/// ```
+pub fn resolve_run_list(
+    api: &ApiClient,
+    environment: &str,
+    run_list: &[RunListItem],
+) -> Result<Vec<CookbookVersion>, Error> {
+    let items: Vec<String> = run_list.iter().map(|i| i.to_string()).collect();
+    let body = json!({ "run_list": items });
+
+    let (status, body) = api
+        .environments()
+        .environment(environment)
+        .cookbook_versions()
+        .execute_raw(Some(&body), "post")?;
+    match status {
+        200..=299 => {
+            let solution: HashMap<String, CookbookVersion> = serde_json::from_value(body)?;
+            let mut cookbooks: Vec<CookbookVersion> = solution
+                .into_iter()
+                .map(|(name, mut cookbook)| {
+                    if cookbook.cookbook_name.is_empty() {
+                        cookbook.cookbook_name = name;
+                    }
+                    cookbook
+                })
+                .collect();
+            cookbooks.sort_by(|a, b| a.cookbook_name.cmp(&b.cookbook_name));
+            Ok(cookbooks)
+        }
+        412 => Err(depsolver_error(&body).into()),
+        _ => Err(ChefError::ChefServerResponseError(status).into()),
+    }
+}
+
+/// Builds a `DepsolverError` from the body of a 412 response. The server reports either a list
+/// of plain messages or a list of objects detailing the failure.
+fn depsolver_error(body: &Value) -> ChefError {
+    let strings = |v: &Value| -> Vec<String> {
+        v.as_array()
+            .map(|a| {
+                a.iter()
+                    .map(|s| match s.as_str() {
+                        Some(s) => s.to_owned(),
+                        None => s.to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let error = match &body["error"] {
+        Value::Array(errors) => errors.first().cloned().unwrap_or(Value::Null),
+        other => other.clone(),
+    };
+    let message = match &error {
+        Value::String(s) => s.clone(),
+        Value::Object(o) => o
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned(),
+        _ => String::from("unknown depsolver failure"),
+    };
+
+    ChefError::DepsolverError {
+        message,
+        unsatisfiable_run_list_item: error["unsatisfiable_run_list_item"]
+            .as_str()
+            .map(String::from),
+        non_existent_cookbooks: strings(&error["non_existent_cookbooks"]),
+        cookbooks_with_no_versions: strings(&error["cookbooks_with_no_versions"]),
+        most_constrained_cookbooks: strings(&error["most_constrained_cookbooks"]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_attrs() {
+        let mut env = Environment::default();
+        env.set_default_attr("nginx.ports", json!([80, 443]))
+            .unwrap();
+        env.set_override_attr("nginx.ports", json!([80])).unwrap();
+        env.set_override_attr("nginx.ports.0", json!(8080)).unwrap();
+        assert!(env.set_override_attr("nginx.ports.3", json!(8443)).is_err());
+        assert_eq!(env.attr("nginx.ports"), Some(json!([8080])));
+        assert_eq!(env.delete_attr("nginx.ports"), Some(json!([8080])));
+        assert!(env.attr("nginx.ports").is_none());
+    }
+
+    #[test]
+    fn test_depsolver_error() {
+        let body = json!({"error": [{
+            "message": "Unable to satisfy constraints on package nginx",
+            "unsatisfiable_run_list_item": "(nginx >= 0.0.0)",
+            "non_existent_cookbooks": ["ohai"],
+            "most_constrained_cookbooks": ["nginx 2.0.0 -> []"]
+        }]});
+        match depsolver_error(&body) {
+            ChefError::DepsolverError {
+                message,
+                unsatisfiable_run_list_item,
+                non_existent_cookbooks,
+                cookbooks_with_no_versions,
+                most_constrained_cookbooks,
+            } => {
+                assert_eq!(message, "Unable to satisfy constraints on package nginx");
+                assert_eq!(
+                    unsatisfiable_run_list_item,
+                    Some(String::from("(nginx >= 0.0.0)"))
+                );
+                assert_eq!(non_existent_cookbooks, vec!["ohai"]);
+                assert!(cookbooks_with_no_versions.is_empty());
+                assert_eq!(most_constrained_cookbooks, vec!["nginx 2.0.0 -> []"]);
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+
+        let body = json!({"error": ["Run list contains invalid items: no such cookbook nope."]});
+        assert_eq!(
+            depsolver_error(&body).to_string(),
+            "Unable to solve cookbook dependencies: \
+             Run list contains invalid items: no such cookbook nope."
+        );
+    }
+}
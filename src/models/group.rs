@@ -0,0 +1,210 @@
+model_use!();
+
+use crate::models::Principal;
+use chef_api::api_client::{ApiClient, Execute};
+use std::collections::BTreeSet;
+
+/// A group within an organization, and its direct members.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Group {
+    pub name: String,
+    pub groupname: String,
+    pub orgname: String,
+    pub actors: Vec<String>,
+    pub users: Vec<String>,
+    pub clients: Vec<String>,
+    pub groups: Vec<String>,
+}
+
+model_impl!(Group);
+model_list!(GroupList);
+
+#[derive(Debug, Serialize)]
+struct GroupUpdate<'a> {
+    groupname: &'a str,
+    actors: GroupMembers<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct GroupMembers<'a> {
+    users: &'a [String],
+    clients: &'a [String],
+    groups: &'a [String],
+}
+
+impl Group {
+    fn members_mut(&mut self, principal: &Principal) -> &mut Vec<String> {
+        match *principal {
+            Principal::User(_) => &mut self.users,
+            Principal::Client(_) => &mut self.clients,
+            Principal::Group(_) => &mut self.groups,
+        }
+    }
+
+    /// Adds a user, client or nested group. Returns false if it was already a member.
+    pub fn add(&mut self, principal: &Principal) -> bool {
+        let name = principal.name();
+        let members = self.members_mut(principal);
+        if members.iter().any(|m| m == name) {
+            return false;
+        }
+        members.push(name.into());
+        if let Principal::User(_) | Principal::Client(_) = principal {
+            self.actors.push(name.into());
+        }
+        true
+    }
+
+    /// Removes a user, client or nested group. Returns false if it was not a member.
+    pub fn remove(&mut self, principal: &Principal) -> bool {
+        let name = principal.name();
+        let members = self.members_mut(principal);
+        let before = members.len();
+        members.retain(|m| m != name);
+        if members.len() == before {
+            return false;
+        }
+        if let Principal::User(_) | Principal::Client(_) = principal {
+            self.actors.retain(|a| a != name);
+        }
+        true
+    }
+
+    fn group_name(&self) -> &str {
+        if self.groupname.is_empty() {
+            &self.name
+        } else {
+            &self.groupname
+        }
+    }
+}
+
+/// The effective membership of a group, with nested groups expanded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EffectiveMembers {
+    /// Every user or client that is a member, directly or through a nested group.
+    pub actors: BTreeSet<String>,
+    pub users: BTreeSet<String>,
+    pub clients: BTreeSet<String>,
+    /// The group itself and every group nested inside it.
+    pub groups: BTreeSet<String>,
+}
+
+/// Fetches a group.
+pub fn get_group(api: &ApiClient, name: &str) -> Result<Group, Error> {
+    api.groups().group(name).get().and_then(Group::try_from)
+}
+
+/// Saves the membership of a group.
+pub fn update_group(api: &ApiClient, group: &Group) -> Result<(), Error> {
+    let name = group.group_name();
+    let body = GroupUpdate {
+        groupname: name,
+        actors: GroupMembers {
+            users: &group.users,
+            clients: &group.clients,
+            groups: &group.groups,
+        },
+    };
+    api.groups().group(name).put(&body).map(|_| ())
+}
+
+/// Adds a user, client or nested group to the named group.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// add_to_group(&api, "admins", Principal::User("barney")).unwrap();
+/// ```
+pub fn add_to_group(api: &ApiClient, group: &str, principal: Principal) -> Result<(), Error> {
+    let mut group = get_group(api, group)?;
+    if group.add(&principal) {
+        update_group(api, &group)?;
+    }
+    Ok(())
+}
+
+/// Removes a user, client or nested group from the named group.
+pub fn remove_from_group(api: &ApiClient, group: &str, principal: Principal) -> Result<(), Error> {
+    let mut group = get_group(api, group)?;
+    if group.remove(&principal) {
+        update_group(api, &group)?;
+    }
+    Ok(())
+}
+
+/// Expands the named group, and any groups nested within it, into the effective set of members.
+/// Each group is fetched at most once, so membership cycles are harmless.
+pub fn resolve_group_members(api: &ApiClient, group: &str) -> Result<EffectiveMembers, Error> {
+    resolve_members(group, |name| get_group(api, name))
+}
+
+fn resolve_members<F>(group: &str, mut fetch: F) -> Result<EffectiveMembers, Error>
+where
+    F: FnMut(&str) -> Result<Group, Error>,
+{
+    let mut members = EffectiveMembers::default();
+    let mut pending = vec![group.to_owned()];
+
+    while let Some(name) = pending.pop() {
+        if !members.groups.insert(name.clone()) {
+            continue;
+        }
+        let group = fetch(&name)?;
+        members.users.extend(group.users.iter().cloned());
+        members.clients.extend(group.clients.iter().cloned());
+        members.actors.extend(group.actors);
+        members.actors.extend(group.users);
+        members.actors.extend(group.clients);
+        pending.extend(group.groups);
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str, users: &[&str], groups: &[&str]) -> Group {
+        Group {
+            name: name.into(),
+            groupname: name.into(),
+            users: users.iter().map(|u| u.to_string()).collect(),
+            actors: users.iter().map(|u| u.to_string()).collect(),
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_add_and_remove_members() {
+        let mut g = group("admins", &["pivotal"], &[]);
+        assert!(g.add(&Principal::Client("web01")));
+        assert!(!g.add(&Principal::Client("web01")));
+        assert!(g.add(&Principal::Group("ops")));
+        assert_eq!(g.actors, vec!["pivotal", "web01"]);
+        assert!(g.remove(&Principal::User("pivotal")));
+        assert!(!g.remove(&Principal::User("pivotal")));
+        assert_eq!(g.actors, vec!["web01"]);
+        assert_eq!(g.groups, vec!["ops"]);
+    }
+
+    #[test]
+    fn test_resolve_nested_members() {
+        let groups = [
+            group("admins", &["barney"], &["ops"]),
+            group("ops", &["fred"], &["oncall", "admins"]),
+            group("oncall", &["wilma", "fred"], &[]),
+        ];
+        let members = resolve_members("admins", |name| {
+            Ok(groups.iter().find(|g| g.name == name).unwrap().clone())
+        })
+        .unwrap();
+
+        let users: Vec<_> = members.users.into_iter().collect();
+        assert_eq!(users, vec!["barney", "fred", "wilma"]);
+        assert_eq!(members.groups.len(), 3);
+    }
+}
@@ -0,0 +1,425 @@
+model_use!();
+
+use crate::models::KeyGeneration;
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+use chrono::{DateTime, Utc};
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// A public key belonging to a client or a user.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Key {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expired: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+}
+
+model_impl!(Key);
+
+impl Key {
+    /// Returns the expiration date of the key, or `None` if the key never expires.
+    pub fn expiration(&self) -> Option<DateTime<Utc>> {
+        self.expiration_date
+            .as_ref()
+            .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+            .map(|d| d.with_timezone(&Utc))
+    }
+}
+
+/// The actor a key belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOwner<'a> {
+    Client(&'a str),
+    User(&'a str),
+}
+
+impl<'a> KeyOwner<'a> {
+    fn name(&self) -> &'a str {
+        match *self {
+            KeyOwner::Client(name) | KeyOwner::User(name) => name,
+        }
+    }
+
+    fn execute<B>(
+        &self,
+        api: &ApiClient,
+        key: Option<&str>,
+        body: Option<&B>,
+        method: &str,
+    ) -> Result<Value, Error>
+    where
+        B: Serialize,
+    {
+        match *self {
+            KeyOwner::Client(name) => {
+                let mut req = api.clients();
+                req.client(name).keys();
+                if let Some(key) = key {
+                    req.key(key);
+                }
+                req.execute(body, method)
+            }
+            KeyOwner::User(name) => {
+                let mut req = api.server_users();
+                req.user(name).keys();
+                if let Some(key) = key {
+                    req.key(key);
+                }
+                req.execute(body, method)
+            }
+        }
+    }
+}
+
+/// What to do with the previous key once a new key has been verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetiredKeyAction {
+    #[default]
+    Delete,
+    Expire,
+}
+
+/// Options for `rotate_key`.
+#[derive(Debug, Clone)]
+pub struct KeyRotateOptions {
+    /// The name of the key being replaced.
+    pub old_key: String,
+    /// The name of the new key.
+    pub new_key: String,
+    /// When the new key expires; `None` means it never expires.
+    pub expiration_date: Option<DateTime<Utc>>,
+    /// How the new key pair is generated.
+    pub key: KeyGeneration,
+    /// What to do with the old key.
+    pub retire: RetiredKeyAction,
+}
+
+impl Default for KeyRotateOptions {
+    fn default() -> Self {
+        KeyRotateOptions {
+            old_key: String::from("default"),
+            new_key: Utc::now().format("key-%Y%m%d%H%M%S").to_string(),
+            expiration_date: None,
+            key: KeyGeneration::Local(2048),
+            retire: RetiredKeyAction::default(),
+        }
+    }
+}
+
+/// A newly created key, along with its private key.
+#[derive(Debug, Clone, Default)]
+pub struct RotatedKey {
+    pub key: Key,
+    pub private_key: String,
+}
+
+#[derive(Debug, Serialize)]
+struct KeyCreateRequest<'a> {
+    name: &'a str,
+    expiration_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_key: Option<bool>,
+}
+
+fn format_expiration(date: Option<DateTime<Utc>>) -> String {
+    match date {
+        Some(date) => date.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        None => String::from("infinity"),
+    }
+}
+
+/// Writes a private key to `path`, readable and writable only by the current user.
+pub(crate) fn write_private_key(path: &Path, key: Option<&String>) -> Result<(), Error> {
+    let key = key.ok_or_else(|| ChefError::KeyMissingError(String::from("private_key")))?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut fh = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fh.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    fh.write_all(key.as_bytes())?;
+    Ok(())
+}
+
+/// Generates an RSA key pair, returning the PEM encoded public and private keys.
+pub(crate) fn generate_key_pair(bits: u32) -> Result<(String, String), Error> {
+    let rsa = Rsa::generate(bits)?;
+    let public_key = String::from_utf8(rsa.public_key_to_pem()?)?;
+    let private_key = String::from_utf8(rsa.private_key_to_pem()?)?;
+    Ok((public_key, private_key))
+}
+
+/// Lists the keys belonging to `owner`.
+pub fn list_keys(api: &ApiClient, owner: KeyOwner) -> Result<Vec<Key>, Error> {
+    let keys = owner.execute(api, None, None::<&Value>, "get")?;
+    serde_json::from_value(keys).map_err(|e| e.into())
+}
+
+/// Fetches a single named key belonging to `owner`.
+pub fn get_key(api: &ApiClient, owner: KeyOwner, name: &str) -> Result<Key, Error> {
+    owner
+        .execute(api, Some(name), None::<&Value>, "get")
+        .and_then(Key::try_from)
+}
+
+/// Deletes a named key belonging to `owner`.
+pub fn delete_key(api: &ApiClient, owner: KeyOwner, name: &str) -> Result<(), Error> {
+    owner
+        .execute(api, Some(name), None::<&Value>, "delete")
+        .map(|_| ())
+}
+
+/// Replaces the key `opts.old_key` of `owner` with a new key.
+///
+/// The new key is created first, and then used to make an authenticated request as `owner`. Only
+/// once that succeeds is the old key deleted or expired; if it fails the new key is removed again
+/// and a `KeyVerificationError` giving the reason is returned.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # use chrono::{Duration, Utc};
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let opts = KeyRotateOptions {
+///     expiration_date: Some(Utc::now() + Duration::days(90)),
+///     ..Default::default()
+/// };
+/// let rotated = rotate_key(&api, KeyOwner::Client("my_client"), &opts).unwrap();
+/// ```
+pub fn rotate_key(
+    api: &ApiClient,
+    owner: KeyOwner,
+    opts: &KeyRotateOptions,
+) -> Result<RotatedKey, Error> {
+    let (body, local_key) = key_create_request(opts)?;
+    let resp = owner.execute(api, None, Some(&body), "post")?;
+    let private_key = match local_key {
+        Some(key) => key,
+        None => resp
+            .get("private_key")
+            .and_then(Value::as_str)
+            .map(String::from)
+            .ok_or_else(|| ChefError::KeyMissingError(String::from("private_key")))?,
+    };
+
+    if let Err(e) = verify_key(api, owner, &private_key) {
+        let cleanup = delete_key(api, owner, &opts.new_key);
+        return Err(verification_error(&opts.new_key, e, cleanup));
+    }
+    // Fetching the new key with `api` could fail once the old key is retired, so the key is
+    // described from what was posted instead.
+    let key = created_key(body, &resp, &private_key);
+
+    match opts.retire {
+        RetiredKeyAction::Delete => delete_key(api, owner, &opts.old_key)?,
+        RetiredKeyAction::Expire => {
+            let old = Key {
+                name: opts.old_key.clone(),
+                expiration_date: Some(format_expiration(Some(Utc::now()))),
+                ..Default::default()
+            };
+            owner.execute(api, Some(&opts.old_key), Some(&old), "put")?;
+        }
+    }
+
+    Ok(RotatedKey { key, private_key })
+}
+
+/// Describes the key created by `body`, given the response to creating it and its private key.
+fn created_key(body: KeyCreateRequest<'_>, resp: &Value, private_key: &str) -> Key {
+    let public_key = body.public_key.or_else(|| {
+        PKey::private_key_from_pem(private_key.as_bytes())
+            .and_then(|k| k.public_key_to_pem())
+            .ok()
+            .and_then(|pem| String::from_utf8(pem).ok())
+    });
+    Key {
+        name: body.name.into(),
+        public_key,
+        expiration_date: Some(body.expiration_date),
+        expired: Some(false),
+        uri: resp.get("uri").and_then(Value::as_str).map(String::from),
+    }
+}
+
+/// Builds the request that creates the new key, along with the private key if it was generated
+/// locally.
+fn key_create_request(
+    opts: &KeyRotateOptions,
+) -> Result<(KeyCreateRequest<'_>, Option<String>), Error> {
+    let mut body = KeyCreateRequest {
+        name: &opts.new_key,
+        expiration_date: format_expiration(opts.expiration_date),
+        public_key: None,
+        create_key: None,
+    };
+
+    let local_key = match opts.key {
+        KeyGeneration::Server => {
+            body.create_key = Some(true);
+            None
+        }
+        KeyGeneration::Local(bits) => {
+            let (public_key, private_key) = generate_key_pair(bits)?;
+            body.public_key = Some(public_key);
+            Some(private_key)
+        }
+    };
+    Ok((body, local_key))
+}
+
+/// Builds the error returned when a new key fails verification. The verification failure is
+/// kept as the reason, even if removing the new key failed as well.
+fn verification_error(new_key: &str, cause: Error, cleanup: Result<(), Error>) -> Error {
+    let mut reason = cause.to_string();
+    if let Err(e) = cleanup {
+        log::warn!("Unable to remove the unverified key {}: {}", new_key, e);
+        reason = format!("{}; removing the key also failed: {}", reason, e);
+    }
+    ChefError::KeyVerificationError(new_key.into(), reason).into()
+}
+
+fn verify_key(api: &ApiClient, owner: KeyOwner, private_key: &str) -> Result<(), Error> {
+    let config = api.config.with_identity(owner.name(), private_key);
+    let verifier = ApiClient::new(config)?;
+    match owner {
+        KeyOwner::Client(name) => verifier.clients().client(name).get(),
+        KeyOwner::User(name) => verifier.server_users().user(name).get(),
+    }
+    .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    #[test]
+    fn test_key_expiration() {
+        let key: Key = serde_json::from_str(
+            r#"{"name": "default", "expiration_date": "2020-01-02T03:04:05Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            key.expiration().unwrap(),
+            Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap()
+        );
+
+        let key: Key =
+            serde_json::from_str(r#"{"name": "default", "expiration_date": "infinity"}"#).unwrap();
+        assert!(key.expiration().is_none());
+    }
+
+    #[test]
+    fn test_key_create_request() {
+        let opts = KeyRotateOptions {
+            new_key: String::from("key-2"),
+            expiration_date: Some(Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 5).unwrap()),
+            key: KeyGeneration::Local(1024),
+            ..Default::default()
+        };
+        let (body, private_key) = key_create_request(&opts).unwrap();
+        let body = serde_json::to_value(&body).unwrap();
+        assert_eq!(body["name"], "key-2");
+        assert_eq!(body["expiration_date"], "2030-01-02T03:04:05Z");
+        assert!(body.get("create_key").is_none());
+        let public_key = body["public_key"].as_str().unwrap();
+        let private_key =
+            openssl::pkey::PKey::private_key_from_pem(private_key.unwrap().as_bytes()).unwrap();
+        assert_eq!(
+            private_key.public_key_to_pem().unwrap(),
+            public_key.as_bytes()
+        );
+
+        let opts = KeyRotateOptions {
+            key: KeyGeneration::Server,
+            ..Default::default()
+        };
+        let (body, private_key) = key_create_request(&opts).unwrap();
+        let body = serde_json::to_value(&body).unwrap();
+        assert_eq!(body["create_key"], true);
+        assert_eq!(body["expiration_date"], "infinity");
+        assert!(private_key.is_none());
+    }
+
+    #[test]
+    fn test_created_key() {
+        let opts = KeyRotateOptions {
+            new_key: String::from("key-2"),
+            key: KeyGeneration::Local(1024),
+            ..Default::default()
+        };
+        let (body, private_key) = key_create_request(&opts).unwrap();
+        let public_key = body.public_key.clone();
+        let resp = json!({"uri": "https://chef/organizations/bedrock/clients/barney/keys/key-2"});
+        let key = created_key(body, &resp, &private_key.unwrap());
+        assert_eq!(key.name, "key-2");
+        assert_eq!(key.public_key, public_key);
+        assert_eq!(key.expiration_date.as_deref(), Some("infinity"));
+        assert!(key.uri.unwrap().ends_with("/keys/key-2"));
+
+        // Keys generated by the Chef Server are only returned as private keys.
+        let (private, public) = {
+            let rsa = openssl::rsa::Rsa::generate(1024).unwrap();
+            (
+                String::from_utf8(rsa.private_key_to_pem().unwrap()).unwrap(),
+                String::from_utf8(PKey::from_rsa(rsa).unwrap().public_key_to_pem().unwrap())
+                    .unwrap(),
+            )
+        };
+        let opts = KeyRotateOptions {
+            key: KeyGeneration::Server,
+            ..Default::default()
+        };
+        let (body, _) = key_create_request(&opts).unwrap();
+        let key = created_key(body, &json!({"private_key": private.clone()}), &private);
+        assert_eq!(key.public_key, Some(public));
+        assert!(key.uri.is_none());
+    }
+
+    #[test]
+    fn test_verification_error() {
+        let err = verification_error(
+            "key-2",
+            ChefError::ChefServerResponseError(401).into(),
+            Err(ChefError::ChefServerResponseError(500).into()),
+        );
+        match err.downcast_ref::<ChefError>() {
+            Some(ChefError::KeyVerificationError(name, reason)) => {
+                assert_eq!(name, "key-2");
+                assert!(reason.contains("401"));
+                assert!(reason.contains("500"));
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+        let err = verification_error(
+            "key-2",
+            ChefError::ChefServerResponseError(401).into(),
+            Ok(()),
+        );
+        assert!(err.to_string().ends_with("error code: 401"));
+    }
+}
@@ -112,6 +112,7 @@ macro_rules! model_impl {
 
 macro_rules! model_use {
     () => {
+        #[allow(unused_imports)]
         use chef_api::utils::decode_list;
         use failure::Error;
 
@@ -126,6 +127,7 @@ macro_rules! model_use {
     };
 }
 
+pub mod attributes;
 pub mod node;
 pub use self::node::*;
 pub mod environment;
@@ -136,9 +138,31 @@ pub mod data_bag;
 pub use self::data_bag::*;
 pub mod data_bag_item;
 pub use self::data_bag_item::*;
+pub mod encrypted_data_bag_item;
+pub use self::encrypted_data_bag_item::*;
+pub mod vault;
+pub use self::vault::*;
 pub mod cookbook;
 pub use self::cookbook::*;
 pub mod client;
 pub use self::client::*;
+pub mod key;
+pub use self::key::*;
+pub mod acl;
+pub use self::acl::*;
+pub mod group;
+pub use self::group::*;
+pub mod organization;
+pub use self::organization::*;
+pub mod user;
+pub use self::user::*;
+pub mod association_request;
+pub use self::association_request::*;
+pub mod run_list;
+pub use self::run_list::*;
+pub mod version;
+pub use self::version::*;
+pub mod universe;
+pub use self::universe::*;
 pub mod partial_result;
 pub use self::partial_result::*;
@@ -1,5 +1,12 @@
 model_use!();
 
+use crate::models::attributes::{
+    self, delete_merged_attr, from_value, hash_only_merge, merge_level, merged_attr, to_value,
+    AttributePath, Attributes,
+};
+use crate::models::{Environment, Role};
+use serde::de::DeserializeOwned;
+
 chef_json_type!(NodeJsonClass, "Chef::Node");
 chef_json_type!(NodeChefType, "node");
 
@@ -28,3 +35,147 @@ pub struct Node {
 model_impl!(Node);
 model_list!(NodeList);
 model_result!(Node, NodeResult);
+
+impl Node {
+    /// Returns the attributes a recipe running on this node would see, merging the `default`,
+    /// `normal`, `override` and `automatic` levels with Chef's precedence rules.
+    pub fn merged_attributes(&self) -> HashMap<String, Value> {
+        self.merged_attributes_with(&[], None)
+    }
+
+    /// Like `merged_attributes`, but also applies the default and override attributes of the
+    /// given roles, in run list order, and of the node's environment.
+    ///
+    /// Following Chef, role defaults take precedence over environment defaults, while
+    /// environment overrides take precedence over role overrides.
+    ///
+    /// ```rust,no_run
+    /// # use chef::models::*;
+    /// # let (node, web, env) = (Node::default(), Role::default(), Environment::default());
+    /// let attrs = node.merged_attributes_with(&[web], Some(&env));
+    /// println!("{:?}", attrs.get("nginx"));
+    /// ```
+    pub fn merged_attributes_with(
+        &self,
+        roles: &[Role],
+        environment: Option<&Environment>,
+    ) -> HashMap<String, Value> {
+        let mut defaults = vec![&self.default];
+        defaults.extend(environment.map(|e| &e.default_attributes));
+        defaults.extend(roles.iter().map(|r| &r.default_attributes));
+
+        let mut overrides = vec![&self.overrides];
+        overrides.extend(roles.iter().map(|r| &r.override_attributes));
+        overrides.extend(environment.map(|e| &e.override_attributes));
+
+        let levels = [
+            merge_level(defaults),
+            to_value(&self.normal),
+            merge_level(overrides),
+            to_value(&self.automatic),
+        ];
+        from_value(levels.iter().fold(Value::Null, hash_only_merge))
+    }
+
+    /// Returns the merged value of the attribute at `path`, as a recipe would see it.
+    ///
+    /// ```rust,no_run
+    /// # use chef::models::*;
+    /// # let node = Node::default();
+    /// let eth0 = node.attr("network.interfaces.eth0");
+    /// let family: String = node
+    ///     .attr_as(["network", "interfaces", "eth0", "addresses", "10.0.0.1", "family"])
+    ///     .unwrap();
+    /// ```
+    pub fn attr<P: AttributePath>(&self, path: P) -> Option<Value> {
+        merged_attr(
+            &[
+                &self.default,
+                &self.normal,
+                &self.overrides,
+                &self.automatic,
+            ],
+            path,
+        )
+    }
+
+    /// Deserializes the merged value of the attribute at `path` into a `T`.
+    pub fn attr_as<T, P>(&self, path: P) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+        P: AttributePath,
+    {
+        attributes::attr_as(self.attr(&path), path)
+    }
+
+    /// Sets a normal attribute at `path`, as `Attributes::set_attr` does.
+    pub fn set_attr<P: AttributePath>(&mut self, path: P, value: Value) -> Result<(), Error> {
+        self.normal.set_attr(path, value)
+    }
+
+    /// Removes the attribute at `path` from every precedence level, returning its merged value.
+    pub fn delete_attr<P: AttributePath>(&mut self, path: P) -> Option<Value> {
+        delete_merged_attr(
+            &mut [
+                &mut self.default,
+                &mut self.normal,
+                &mut self.overrides,
+                &mut self.automatic,
+            ],
+            path,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merged_attributes() {
+        let node: Node = serde_json::from_value(json!({
+            "name": "web01",
+            "default": {"port": 80, "users": ["fred"], "tuning": {"workers": 2}},
+            "normal": {"users": ["barney"]},
+            "override": {"tuning": {"workers": 4}},
+            "automatic": {"hostname": "web01"}
+        }))
+        .unwrap();
+        let role: Role = serde_json::from_value(json!({
+            "default_attributes": {"port": 8080, "users": ["wilma"]},
+            "override_attributes": {"tuning": {"workers": 8, "keepalive": true}}
+        }))
+        .unwrap();
+        let env: Environment = serde_json::from_value(json!({
+            "default_attributes": {"port": 8000},
+            "override_attributes": {"tuning": {"workers": 16}}
+        }))
+        .unwrap();
+
+        let attrs = node.merged_attributes_with(&[role], Some(&env));
+        assert_eq!(attrs["port"], json!(8080));
+        assert_eq!(attrs["users"], json!(["barney"]));
+        assert_eq!(attrs["tuning"], json!({"workers": 16, "keepalive": true}));
+        assert_eq!(attrs["hostname"], json!("web01"));
+    }
+
+    #[test]
+    fn test_node_attr() {
+        let mut node: Node = serde_json::from_value(json!({
+            "default": {"nginx": {"port": 80, "user": "www"}},
+            "normal": {"nginx": {"port": 8080}}
+        }))
+        .unwrap();
+        assert_eq!(node.attr_as::<u16, _>("nginx.port").unwrap(), 8080);
+        assert_eq!(
+            node.attr("nginx"),
+            Some(json!({"port": 8080, "user": "www"}))
+        );
+
+        node.set_attr("nginx.user", json!("nginx")).unwrap();
+        assert_eq!(node.attr("nginx.user"), Some(json!("nginx")));
+        assert_eq!(node.delete_attr("nginx.port"), Some(json!(8080)));
+        assert!(node.attr("nginx.port").is_none());
+    }
+}
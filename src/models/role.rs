@@ -1,5 +1,8 @@
 model_use!();
 
+use crate::models::attributes::{self, delete_merged_attr, merged_attr, AttributePath, Attributes};
+use serde::de::DeserializeOwned;
+
 chef_json_type!(RoleJsonClass, "Chef::Role");
 chef_json_type!(RoleChefType, "role");
 
@@ -17,6 +20,8 @@ pub struct Role {
     #[serde(default)]
     pub run_list: Vec<String>,
     #[serde(default)]
+    pub env_run_lists: HashMap<String, Vec<String>>,
+    #[serde(default)]
     pub default_attributes: HashMap<String, Value>,
     #[serde(default)]
     pub override_attributes: HashMap<String, Value>,
@@ -25,3 +30,55 @@ pub struct Role {
 model_impl!(Role);
 model_list!(RoleList);
 model_result!(Role, RoleResult);
+
+impl Role {
+    /// Returns the run list to use in `environment`, falling back to the default run list if the
+    /// role has no run list specific to it.
+    pub fn run_list_for(&self, environment: &str) -> &[String] {
+        self.env_run_lists
+            .get(environment)
+            .unwrap_or(&self.run_list)
+    }
+
+    /// Returns the value of the attribute at `path`, with override attributes taking precedence
+    /// over default attributes.
+    pub fn attr<P: AttributePath>(&self, path: P) -> Option<Value> {
+        merged_attr(&[&self.default_attributes, &self.override_attributes], path)
+    }
+
+    /// Deserializes the value of the attribute at `path` into a `T`.
+    pub fn attr_as<T, P>(&self, path: P) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+        P: AttributePath,
+    {
+        attributes::attr_as(self.attr(&path), path)
+    }
+
+    /// Sets a default attribute at `path`, as `Attributes::set_attr` does.
+    pub fn set_default_attr<P: AttributePath>(
+        &mut self,
+        path: P,
+        value: Value,
+    ) -> Result<(), Error> {
+        self.default_attributes.set_attr(path, value)
+    }
+
+    /// Sets an override attribute at `path`, as `Attributes::set_attr` does.
+    pub fn set_override_attr<P: AttributePath>(
+        &mut self,
+        path: P,
+        value: Value,
+    ) -> Result<(), Error> {
+        self.override_attributes.set_attr(path, value)
+    }
+
+    /// Removes the attribute at `path` from both the default and override attributes, returning
+    /// its merged value.
+    pub fn delete_attr<P: AttributePath>(&mut self, path: P) -> Option<Value> {
+        delete_merged_attr(
+            &mut [&mut self.default_attributes, &mut self.override_attributes],
+            path,
+        )
+    }
+}
@@ -0,0 +1,316 @@
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+use failure::Error;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::models::{Node, Role};
+
+/// A single entry of a run list: either a recipe, optionally pinned to a cookbook version, or a
+/// role.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RunListItem {
+    Recipe {
+        cookbook: String,
+        recipe: Option<String>,
+        version: Option<String>,
+    },
+    Role(String),
+}
+
+fn valid_name(name: &str, allow_dot: bool) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || (allow_dot && c == '.'))
+}
+
+fn valid_version(version: &str) -> bool {
+    let parts: Vec<&str> = version.split('.').collect();
+    (2..=3).contains(&parts.len())
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+impl RunListItem {
+    /// Returns true if this item is a recipe.
+    pub fn is_recipe(&self) -> bool {
+        matches!(*self, RunListItem::Recipe { .. })
+    }
+
+    /// Returns true if this item is a role.
+    pub fn is_role(&self) -> bool {
+        matches!(*self, RunListItem::Role(_))
+    }
+
+    /// The name of the recipe, `default` if only a cookbook was given. `None` for roles.
+    pub fn recipe_name(&self) -> Option<&str> {
+        match *self {
+            RunListItem::Recipe { ref recipe, .. } => Some(recipe.as_deref().unwrap_or("default")),
+            RunListItem::Role(_) => None,
+        }
+    }
+
+    fn parse_recipe(item: &str, original: &str) -> Result<Self, Error> {
+        let invalid = || ChefError::InvalidRunListItemError(original.into());
+        let (name, version) = match item.find('@') {
+            Some(i) => (&item[..i], Some(&item[i + 1..])),
+            None => (item, None),
+        };
+        if let Some(version) = version {
+            if !valid_version(version) {
+                return Err(invalid().into());
+            }
+        }
+        let (cookbook, recipe) = match name.find("::") {
+            Some(i) => (&name[..i], Some(&name[i + 2..])),
+            None => (name, None),
+        };
+        if !valid_name(cookbook, true) || recipe.is_some_and(|r| !valid_name(r, true)) {
+            return Err(invalid().into());
+        }
+        Ok(RunListItem::Recipe {
+            cookbook: cookbook.into(),
+            recipe: recipe.map(String::from),
+            version: version.map(String::from),
+        })
+    }
+}
+
+impl FromStr for RunListItem {
+    type Err = Error;
+
+    /// Parses `recipe[cookbook::recipe@1.0.0]`, `role[name]`, or an unqualified recipe name.
+    fn from_str(item: &str) -> Result<Self, Error> {
+        let invalid = || ChefError::InvalidRunListItemError(item.into());
+        let item = item.trim();
+        if let Some(inner) = item.strip_prefix("role[") {
+            let name = inner.strip_suffix(']').ok_or_else(invalid)?;
+            if !valid_name(name, false) {
+                return Err(invalid().into());
+            }
+            Ok(RunListItem::Role(name.into()))
+        } else if let Some(inner) = item.strip_prefix("recipe[") {
+            let inner = inner.strip_suffix(']').ok_or_else(invalid)?;
+            RunListItem::parse_recipe(inner, item)
+        } else if item.contains('[') || item.contains(']') {
+            Err(invalid().into())
+        } else {
+            RunListItem::parse_recipe(item, item)
+        }
+    }
+}
+
+impl fmt::Display for RunListItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RunListItem::Role(ref name) => write!(f, "role[{}]", name),
+            RunListItem::Recipe {
+                ref cookbook,
+                ref recipe,
+                ref version,
+            } => {
+                write!(f, "recipe[{}", cookbook)?;
+                if let Some(recipe) = recipe {
+                    write!(f, "::{}", recipe)?;
+                }
+                if let Some(version) = version {
+                    write!(f, "@{}", version)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for RunListItem {
+    type Error = Error;
+
+    fn try_from(item: String) -> Result<Self, Error> {
+        item.parse()
+    }
+}
+
+impl From<RunListItem> for String {
+    fn from(item: RunListItem) -> String {
+        item.to_string()
+    }
+}
+
+/// Parses and validates every entry of a run list.
+pub fn parse_run_list<S: AsRef<str>>(items: &[S]) -> Result<Vec<RunListItem>, Error> {
+    items.iter().map(|i| i.as_ref().parse()).collect()
+}
+
+/// The result of expanding a run list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunListExpansion {
+    /// The recipes to run, in order, with duplicates removed.
+    pub recipes: Vec<RunListItem>,
+    /// Every role that was applied, in the order they were expanded.
+    pub roles: Vec<String>,
+}
+
+/// Expands a run list for `environment`, fetching each role from the Chef Server.
+///
+/// As in Chef, each role is only applied once, so roles that include each other do not cause an
+/// endless expansion.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let run_list = parse_run_list(&["role[web]", "recipe[monitoring]"]).unwrap();
+/// let expansion = expand_run_list(&api, &run_list, "production").unwrap();
+/// for recipe in expansion.recipes {
+///     println!("{}", recipe);
+/// }
+/// ```
+pub fn expand_run_list(
+    api: &ApiClient,
+    run_list: &[RunListItem],
+    environment: &str,
+) -> Result<RunListExpansion, Error> {
+    expand_run_list_with(run_list, environment, |name| {
+        api.roles().role(name).get().and_then(Role::try_from)
+    })
+}
+
+/// Expands a run list for `environment`, using `fetch` to look up roles.
+pub fn expand_run_list_with<F>(
+    run_list: &[RunListItem],
+    environment: &str,
+    mut fetch: F,
+) -> Result<RunListExpansion, Error>
+where
+    F: FnMut(&str) -> Result<Role, Error>,
+{
+    let mut expansion = RunListExpansion::default();
+    let mut applied = HashSet::new();
+    expand_items(
+        run_list,
+        environment,
+        &mut fetch,
+        &mut applied,
+        &mut expansion,
+    )?;
+    Ok(expansion)
+}
+
+fn expand_items<F>(
+    items: &[RunListItem],
+    environment: &str,
+    fetch: &mut F,
+    applied: &mut HashSet<String>,
+    expansion: &mut RunListExpansion,
+) -> Result<(), Error>
+where
+    F: FnMut(&str) -> Result<Role, Error>,
+{
+    for item in items {
+        match *item {
+            RunListItem::Recipe { .. } => {
+                if !expansion.recipes.contains(item) {
+                    expansion.recipes.push(item.clone());
+                }
+            }
+            RunListItem::Role(ref name) => {
+                if !applied.insert(name.clone()) {
+                    debug!("Role {} has already been applied, skipping", name);
+                    continue;
+                }
+                expansion.roles.push(name.clone());
+                let role = fetch(name)?;
+                let nested = parse_run_list(role.run_list_for(environment))?;
+                expand_items(&nested, environment, fetch, applied, expansion)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Node {
+    /// Parses the node's run list.
+    pub fn parsed_run_list(&self) -> Result<Vec<RunListItem>, Error> {
+        parse_run_list(&self.run_list)
+    }
+
+    /// Expands the node's run list in the node's environment.
+    pub fn expand_run_list(&self, api: &ApiClient) -> Result<RunListExpansion, Error> {
+        let environment = if self.chef_environment.is_empty() {
+            "_default"
+        } else {
+            &self.chef_environment
+        };
+        expand_run_list(api, &self.parsed_run_list()?, environment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_items() {
+        let item: RunListItem = "recipe[apache2::mod_ssl@1.2.3]".parse().unwrap();
+        assert_eq!(
+            item,
+            RunListItem::Recipe {
+                cookbook: "apache2".into(),
+                recipe: Some("mod_ssl".into()),
+                version: Some("1.2.3".into()),
+            }
+        );
+        assert_eq!(item.to_string(), "recipe[apache2::mod_ssl@1.2.3]");
+
+        let item: RunListItem = "ntp".parse().unwrap();
+        assert_eq!(item.recipe_name(), Some("default"));
+        assert_eq!(item.to_string(), "recipe[ntp]");
+
+        let item: RunListItem = "role[web]".parse().unwrap();
+        assert!(item.is_role());
+
+        for bad in &[
+            "role[web",
+            "recipe[a@1]",
+            "recipe[a@x.y]",
+            "role[a.b]",
+            "thing[x]",
+        ] {
+            assert!(bad.parse::<RunListItem>().is_err(), "{} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn test_expand_run_list() {
+        let mut roles = HashMap::new();
+        let web: Role = serde_json::from_value(serde_json::json!({
+            "name": "web",
+            "run_list": ["role[base]", "recipe[nginx]"],
+            "env_run_lists": {"production": ["role[base]", "recipe[nginx]", "recipe[waf]"]}
+        }))
+        .unwrap();
+        let base: Role = serde_json::from_value(serde_json::json!({
+            "name": "base",
+            "run_list": ["recipe[ntp]", "role[web]"]
+        }))
+        .unwrap();
+        roles.insert("web", web);
+        roles.insert("base", base);
+
+        let run_list = parse_run_list(&["role[web]", "recipe[ntp]"]).unwrap();
+        let fetch = |name: &str| Ok(roles[name].clone());
+        let expansion = expand_run_list_with(&run_list, "production", fetch).unwrap();
+        let recipes: Vec<String> = expansion.recipes.iter().map(|r| r.to_string()).collect();
+        assert_eq!(recipes, vec!["recipe[ntp]", "recipe[nginx]", "recipe[waf]"]);
+        assert_eq!(expansion.roles, vec!["web", "base"]);
+
+        let expansion = expand_run_list_with(&run_list, "staging", fetch).unwrap();
+        assert_eq!(expansion.recipes.len(), 2);
+    }
+}
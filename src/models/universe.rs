@@ -0,0 +1,536 @@
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+use failure::Error;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+
+use crate::models::{Environment, RunListItem, Version, VersionConstraint};
+
+/// A single version of a cookbook in the universe.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct UniverseEntry {
+    pub location_path: String,
+    pub location_type: String,
+    pub dependencies: HashMap<String, VersionConstraint>,
+}
+
+/// Every version of every cookbook on the Chef Server, along with their dependencies, as
+/// returned by `/universe`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(transparent)]
+pub struct Universe {
+    pub cookbooks: HashMap<String, BTreeMap<Version, UniverseEntry>>,
+}
+
+impl TryFrom<Value> for Universe {
+    type Error = Error;
+
+    fn try_from(universe: Value) -> Result<Self, Error> {
+        Ok(serde_json::from_value(universe)?)
+    }
+}
+
+/// Fetches the dependency universe from the Chef Server.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::{ApiClient, Execute};
+/// # use chef::models::*;
+/// # use std::convert::TryFrom;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let universe = get_universe(&api).unwrap();
+/// let env = api.environments().environment("production").get().unwrap();
+/// let env = Environment::try_from(env).unwrap();
+/// let run_list = parse_run_list(&["recipe[nginx]", "recipe[monitoring]"]).unwrap();
+/// for (cookbook, version) in universe.solve_for_environment(&run_list, &env).unwrap() {
+///     println!("{} {}", cookbook, version);
+/// }
+/// ```
+pub fn get_universe(api: &ApiClient) -> Result<Universe, Error> {
+    api.universe().get().and_then(Universe::try_from)
+}
+
+impl Universe {
+    /// Solves the cookbook dependencies of `run_list` using the `cookbook_versions` pins of
+    /// `environment`.
+    pub fn solve_for_environment(
+        &self,
+        run_list: &[RunListItem],
+        environment: &Environment,
+    ) -> Result<BTreeMap<String, Version>, Error> {
+        self.solve(run_list, &environment.cookbook_constraints()?)
+    }
+
+    /// Solves the cookbook dependencies of `run_list`, subject to the `constraints` that apply
+    /// to every cookbook, such as an environment's pins.
+    ///
+    /// Like the Chef Server, the newest versions are preferred, with earlier run list items
+    /// taking priority. Roles must be expanded first, for example with `expand_run_list`.
+    ///
+    /// If there is no solution, a `ChefError::DepsolverError` is returned, naming the first run
+    /// list item that could not be satisfied.
+    pub fn solve(
+        &self,
+        run_list: &[RunListItem],
+        constraints: &HashMap<String, VersionConstraint>,
+    ) -> Result<BTreeMap<String, Version>, Error> {
+        let mut roots = Vec::new();
+        for item in run_list {
+            match *item {
+                RunListItem::Recipe {
+                    ref cookbook,
+                    ref version,
+                    ..
+                } => {
+                    let pin = match version {
+                        Some(version) => Some(format!("= {}", version).parse()?),
+                        None => None,
+                    };
+                    roots.push((item, cookbook.as_str(), pin));
+                }
+                RunListItem::Role(_) => {
+                    return Err(ChefError::InvalidRunListItemError(format!(
+                        "{} must be expanded before solving",
+                        item
+                    ))
+                    .into())
+                }
+            }
+        }
+
+        let non_existent: Vec<String> = roots
+            .iter()
+            .filter(|(_, cookbook, _)| !self.cookbooks.contains_key(*cookbook))
+            .map(|(_, cookbook, _)| cookbook.to_string())
+            .collect();
+        let solver = Solver::new(self, constraints);
+        let no_versions: Vec<String> = roots
+            .iter()
+            .filter(|(_, cookbook, pin)| {
+                self.cookbooks.contains_key(*cookbook)
+                    && solver.candidates(cookbook, pin.iter()).is_empty()
+            })
+            .map(|(_, cookbook, _)| cookbook.to_string())
+            .collect();
+        if !non_existent.is_empty() || !no_versions.is_empty() {
+            let mut problems = Vec::new();
+            if !non_existent.is_empty() {
+                problems.push(format!("no such cookbook {}", non_existent.join(", ")));
+            }
+            if !no_versions.is_empty() {
+                problems.push(format!(
+                    "no versions match the constraints on cookbook {}",
+                    no_versions.join(", ")
+                ));
+            }
+            return Err(ChefError::DepsolverError {
+                message: format!("Run list contains invalid items: {}.", problems.join("; ")),
+                unsatisfiable_run_list_item: None,
+                non_existent_cookbooks: non_existent,
+                cookbooks_with_no_versions: no_versions,
+                most_constrained_cookbooks: Vec::new(),
+            }
+            .into());
+        }
+
+        let mut solver = Solver::new(self, constraints);
+        let state = State::default().require(&roots);
+        if let Some(solution) = solver.search(state) {
+            return Ok(solution);
+        }
+        if solver.steps > MAX_SEARCH_STEPS {
+            return Err(ChefError::DepsolverError {
+                message: format!(
+                    "Gave up solving the run list after trying {} cookbook versions",
+                    MAX_SEARCH_STEPS
+                ),
+                unsatisfiable_run_list_item: None,
+                non_existent_cookbooks: Vec::new(),
+                cookbooks_with_no_versions: Vec::new(),
+                most_constrained_cookbooks: Vec::new(),
+            }
+            .into());
+        }
+
+        // Find the first run list item that makes the run list unsatisfiable, as the Chef
+        // Server does. Adding items only adds constraints, so every prefix before it can be
+        // satisfied and every prefix after it can't, which allows a binary search.
+        let satisfiable = (1..roots.len())
+            .collect::<Vec<usize>>()
+            .partition_point(|&n| {
+                let mut solver = Solver::new(self, constraints);
+                solver
+                    .search(State::default().require(&roots[..n]))
+                    .is_some()
+            });
+        let culprit = roots[satisfiable].0.to_string();
+        Err(ChefError::DepsolverError {
+            message: format!(
+                "Unable to satisfy constraints on package {}",
+                solver.most_constrained.as_deref().unwrap_or_default()
+            ),
+            unsatisfiable_run_list_item: Some(culprit),
+            non_existent_cookbooks: solver.missing.into_iter().collect(),
+            cookbooks_with_no_versions: Vec::new(),
+            most_constrained_cookbooks: solver.most_constrained.into_iter().collect(),
+        }
+        .into())
+    }
+}
+
+/// The number of cookbook versions a single search may try before giving up, so a
+/// pathological universe fails with an error rather than hanging.
+const MAX_SEARCH_STEPS: usize = 100_000;
+
+/// Everything the outcome of searching from a `State` depends on: the cookbooks still to be
+/// chosen, the constraints on the cookbooks reachable from them, and the versions already
+/// chosen for those cookbooks. Versions chosen for unreachable cookbooks can't affect the
+/// outcome, so states that only differ in those share a key.
+type StateKey = (
+    Vec<String>,
+    Vec<(String, Vec<String>)>,
+    Vec<(String, Version)>,
+);
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    pending: Vec<String>,
+    constraints: HashMap<String, Vec<VersionConstraint>>,
+    chosen: BTreeMap<String, Version>,
+}
+
+impl State {
+    fn require(mut self, roots: &[(&RunListItem, &str, Option<VersionConstraint>)]) -> Self {
+        for (_, cookbook, pin) in roots {
+            self.pending.push(cookbook.to_string());
+            self.constraints
+                .entry(cookbook.to_string())
+                .or_default()
+                .extend(pin.iter().cloned());
+        }
+        self.pending.reverse();
+        self
+    }
+}
+
+/// A backtracking search over cookbook versions, newest first.
+///
+/// Backtracking alone is exponential when an early choice can't be blamed for a later failure,
+/// so the keys of states that failed are remembered and never searched again. The search is
+/// also bounded by `MAX_SEARCH_STEPS`.
+struct Solver<'a> {
+    universe: &'a Universe,
+    constraints: &'a HashMap<String, VersionConstraint>,
+    missing: BTreeSet<String>,
+    most_constrained: Option<String>,
+    failed: HashSet<StateKey>,
+    reachable: HashMap<String, BTreeSet<String>>,
+    steps: usize,
+}
+
+impl<'a> Solver<'a> {
+    fn new(universe: &'a Universe, constraints: &'a HashMap<String, VersionConstraint>) -> Self {
+        Self {
+            universe,
+            constraints,
+            missing: BTreeSet::new(),
+            most_constrained: None,
+            failed: HashSet::new(),
+            reachable: HashMap::new(),
+            steps: 0,
+        }
+    }
+
+    /// Every cookbook that any version of `cookbook` may depend on, directly or indirectly,
+    /// including `cookbook` itself.
+    fn reachable(&mut self, cookbook: &str) -> BTreeSet<String> {
+        if let Some(reachable) = self.reachable.get(cookbook) {
+            return reachable.clone();
+        }
+        let mut reachable = BTreeSet::new();
+        let mut queue = vec![cookbook.to_string()];
+        while let Some(cookbook) = queue.pop() {
+            if let Some(versions) = self.universe.cookbooks.get(&cookbook) {
+                for entry in versions.values() {
+                    queue.extend(
+                        entry
+                            .dependencies
+                            .keys()
+                            .filter(|d| !reachable.contains(*d))
+                            .cloned(),
+                    );
+                }
+            }
+            reachable.insert(cookbook);
+        }
+        self.reachable
+            .insert(cookbook.to_string(), reachable.clone());
+        reachable
+    }
+
+    fn state_key(&mut self, state: &State) -> StateKey {
+        let pending: BTreeSet<String> = state
+            .pending
+            .iter()
+            .filter(|c| !state.chosen.contains_key(*c))
+            .cloned()
+            .collect();
+        let mut reachable = BTreeSet::new();
+        for cookbook in &pending {
+            reachable.extend(self.reachable(cookbook));
+        }
+        let constraints = reachable
+            .iter()
+            .filter(|c| !state.chosen.contains_key(*c))
+            .filter_map(|c| {
+                let mut constraints: Vec<String> = state
+                    .constraints
+                    .get(c)?
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect();
+                constraints.sort();
+                constraints.dedup();
+                Some((c.clone(), constraints))
+            })
+            .collect();
+        let chosen = state
+            .chosen
+            .iter()
+            .filter(|(c, _)| reachable.contains(*c))
+            .map(|(c, v)| (c.clone(), *v))
+            .collect();
+        (pending.into_iter().collect(), constraints, chosen)
+    }
+
+    /// The versions of `cookbook` allowed by the global constraints and `extra`, newest first.
+    fn candidates<'c, I>(&self, cookbook: &str, extra: I) -> Vec<Version>
+    where
+        I: IntoIterator<Item = &'c VersionConstraint>,
+    {
+        let extra: Vec<&VersionConstraint> = extra.into_iter().collect();
+        self.universe
+            .cookbooks
+            .get(cookbook)
+            .map(|versions| {
+                versions
+                    .keys()
+                    .rev()
+                    .filter(|v| {
+                        self.constraints
+                            .get(cookbook)
+                            .iter()
+                            .all(|c| c.satisfies(v))
+                            && extra.iter().all(|c| c.satisfies(v))
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn search(&mut self, state: State) -> Option<BTreeMap<String, Version>> {
+        if self.steps > MAX_SEARCH_STEPS {
+            return None;
+        }
+        let key = self.state_key(&state);
+        if self.failed.contains(&key) {
+            return None;
+        }
+        let solution = self.search_from(state);
+        if solution.is_none() && self.steps <= MAX_SEARCH_STEPS {
+            self.failed.insert(key);
+        }
+        solution
+    }
+
+    fn search_from(&mut self, mut state: State) -> Option<BTreeMap<String, Version>> {
+        let cookbook = loop {
+            match state.pending.pop() {
+                None => return Some(state.chosen),
+                Some(cookbook) if state.chosen.contains_key(&cookbook) => continue,
+                Some(cookbook) => break cookbook,
+            }
+        };
+
+        if !self.universe.cookbooks.contains_key(&cookbook) {
+            self.missing.insert(cookbook);
+            return None;
+        }
+
+        let candidates = self.candidates(
+            &cookbook,
+            state.constraints.get(&cookbook).into_iter().flatten(),
+        );
+        'candidates: for version in candidates {
+            self.steps += 1;
+            if self.steps > MAX_SEARCH_STEPS {
+                return None;
+            }
+            let mut next = state.clone();
+            next.chosen.insert(cookbook.clone(), version);
+            let entry = &self.universe.cookbooks[&cookbook][&version];
+            for (dependency, constraint) in &entry.dependencies {
+                if let Some(chosen) = next.chosen.get(dependency) {
+                    if !constraint.satisfies(chosen) {
+                        continue 'candidates;
+                    }
+                } else {
+                    next.pending.push(dependency.clone());
+                }
+                next.constraints
+                    .entry(dependency.clone())
+                    .or_default()
+                    .push(*constraint);
+            }
+            if let Some(solution) = self.search(next) {
+                return Some(solution);
+            }
+        }
+
+        self.most_constrained = Some(cookbook);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::parse_run_list;
+    use serde_json::json;
+
+    fn universe() -> Universe {
+        Universe::try_from(json!({
+            "nginx": {
+                "2.0.0": {"location_path": "", "location_type": "chef_server", "dependencies": {"ohai": "~> 1.0"}},
+                "1.0.0": {"location_path": "", "location_type": "chef_server", "dependencies": {}}
+            },
+            "ohai": {
+                "1.2.0": {"dependencies": {}},
+                "2.0.0": {"dependencies": {}}
+            },
+            "monitoring": {
+                "1.0.0": {"dependencies": {"ohai": ">= 2.0"}}
+            },
+            "broken": {
+                "1.0.0": {"dependencies": {"missing": ">= 0.0.0"}}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_solve() {
+        let universe = universe();
+        let run_list = parse_run_list(&["recipe[nginx]"]).unwrap();
+        let solution = universe.solve(&run_list, &HashMap::new()).unwrap();
+        assert_eq!(solution["nginx"].to_string(), "2.0.0");
+        assert_eq!(solution["ohai"].to_string(), "1.2.0");
+
+        // monitoring needs ohai 2, so nginx has to drop back to 1.0.0
+        let run_list = parse_run_list(&["recipe[nginx]", "recipe[monitoring]"]).unwrap();
+        let solution = universe.solve(&run_list, &HashMap::new()).unwrap();
+        assert_eq!(solution["nginx"].to_string(), "1.0.0");
+        assert_eq!(solution["ohai"].to_string(), "2.0.0");
+
+        let mut pins = HashMap::new();
+        pins.insert(String::from("nginx"), "= 2.0.0".parse().unwrap());
+        match universe.solve(&run_list, &pins).unwrap_err().downcast() {
+            Ok(ChefError::DepsolverError {
+                unsatisfiable_run_list_item,
+                ..
+            }) => assert_eq!(
+                unsatisfiable_run_list_item,
+                Some(String::from("recipe[monitoring]"))
+            ),
+            e => panic!("unexpected result {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_solve_missing() {
+        let universe = universe();
+        let run_list = parse_run_list(&["recipe[broken]"]).unwrap();
+        match universe
+            .solve(&run_list, &HashMap::new())
+            .unwrap_err()
+            .downcast()
+        {
+            Ok(ChefError::DepsolverError {
+                non_existent_cookbooks,
+                ..
+            }) => assert_eq!(non_existent_cookbooks, vec!["missing"]),
+            e => panic!("unexpected result {:?}", e),
+        }
+
+        let run_list = parse_run_list(&["recipe[nope]", "recipe[nginx@3.0.0]"]).unwrap();
+        match universe
+            .solve(&run_list, &HashMap::new())
+            .unwrap_err()
+            .downcast()
+        {
+            Ok(ChefError::DepsolverError {
+                message,
+                non_existent_cookbooks,
+                cookbooks_with_no_versions,
+                ..
+            }) => {
+                assert_eq!(
+                    message,
+                    "Run list contains invalid items: no such cookbook nope; \
+                     no versions match the constraints on cookbook nginx."
+                );
+                assert_eq!(non_existent_cookbooks, vec!["nope"]);
+                assert_eq!(cookbooks_with_no_versions, vec!["nginx"]);
+            }
+            e => panic!("unexpected result {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_solve_remembers_failures() {
+        // Each of the 20 independent cookbooks has two versions, so plain backtracking would
+        // try every one of their 2^20 combinations before blaming `needy`.
+        let mut universe = universe();
+        let mut run_list = Vec::new();
+        for i in 0..20 {
+            let name = format!("independent{}", i);
+            universe.cookbooks.insert(
+                name.clone(),
+                ["1.0.0", "2.0.0"]
+                    .iter()
+                    .map(|v| (v.parse().unwrap(), UniverseEntry::default()))
+                    .collect(),
+            );
+            run_list.push(format!("recipe[{}]", name));
+        }
+        let mut needy = UniverseEntry::default();
+        needy
+            .dependencies
+            .insert(String::from("ohai"), ">= 9.0".parse().unwrap());
+        universe.cookbooks.insert(
+            String::from("needy"),
+            vec![("1.0.0".parse().unwrap(), needy)]
+                .into_iter()
+                .collect(),
+        );
+        run_list.push(String::from("recipe[needy]"));
+
+        let run_list = parse_run_list(&run_list).unwrap();
+        match universe
+            .solve(&run_list, &HashMap::new())
+            .unwrap_err()
+            .downcast()
+        {
+            Ok(ChefError::DepsolverError {
+                unsatisfiable_run_list_item,
+                ..
+            }) => assert_eq!(
+                unsatisfiable_run_list_item,
+                Some(String::from("recipe[needy]"))
+            ),
+            e => panic!("unexpected result {:?}", e),
+        }
+    }
+}
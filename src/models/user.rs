@@ -0,0 +1,210 @@
+model_use!();
+
+use chef_api::api_client::{ApiClient, Execute};
+use std::path::Path;
+
+use crate::models::key::{generate_key_pair, write_private_key};
+use crate::models::KeyGeneration;
+
+/// A user on a Chef Server.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct User {
+    pub username: String,
+    pub display_name: String,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub middle_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+}
+
+model_impl!(User);
+model_list!(UserList);
+
+/// Options for `create_user`. At least one of `password` and `key` should be set, so the new user
+/// can authenticate.
+#[derive(Debug, Clone, Default)]
+pub struct UserCreateOptions {
+    /// The password the user logs in to the web interface with.
+    pub password: Option<String>,
+    /// How to generate the user's key pair, if one should be created.
+    pub key: Option<KeyGeneration>,
+}
+
+#[derive(Debug, Serialize)]
+struct UserCreateRequest<'a> {
+    #[serde(flatten)]
+    user: &'a User,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_key: Option<bool>,
+}
+
+/// A newly created user, along with their private key if one was generated.
+#[derive(Debug, Clone, Default)]
+pub struct CreatedUser {
+    pub username: String,
+    pub uri: Option<String>,
+    pub private_key: Option<String>,
+}
+
+impl CreatedUser {
+    /// Writes the user's private key to `path`, readable and writable only by the current user.
+    pub fn write_private_key<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        write_private_key(path.as_ref(), self.private_key.as_ref())
+    }
+}
+
+/// Creates a new user. Requires server administrator privileges.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let user = User {
+///     username: "barney".into(),
+///     display_name: "Barney Rubble".into(),
+///     email: "barney@bedrock.com".into(),
+///     first_name: "Barney".into(),
+///     last_name: "Rubble".into(),
+///     ..Default::default()
+/// };
+/// let opts = UserCreateOptions { key: Some(KeyGeneration::Server), ..Default::default() };
+/// let created = create_user(&api, &user, &opts).unwrap();
+/// ```
+pub fn create_user(
+    api: &ApiClient,
+    user: &User,
+    opts: &UserCreateOptions,
+) -> Result<CreatedUser, Error> {
+    let mut user = user.clone();
+    let mut private_key = None;
+    let mut create_key = None;
+    match opts.key {
+        Some(KeyGeneration::Server) => create_key = Some(true),
+        Some(KeyGeneration::Local(bits)) => {
+            let (public, private) = generate_key_pair(bits)?;
+            user.public_key = Some(public);
+            private_key = Some(private);
+        }
+        None => (),
+    }
+
+    let body = UserCreateRequest {
+        user: &user,
+        password: opts.password.as_ref().map(String::as_ref),
+        create_key,
+    };
+    let resp = api.server_users().post(&body)?;
+    Ok(created_user(user.username, &resp, private_key))
+}
+
+/// Builds a `CreatedUser` from the server's response, which carries a server generated key
+/// either in `chef_key` (API version 1) or at the top level (API version 0).
+fn created_user(username: String, resp: &Value, private_key: Option<String>) -> CreatedUser {
+    let private_key = private_key.or_else(|| {
+        resp.pointer("/chef_key/private_key")
+            .or_else(|| resp.get("private_key"))
+            .and_then(Value::as_str)
+            .map(String::from)
+    });
+    CreatedUser {
+        username,
+        uri: resp.get("uri").and_then(Value::as_str).map(String::from),
+        private_key,
+    }
+}
+
+/// Fetches a user.
+pub fn get_user(api: &ApiClient, name: &str) -> Result<User, Error> {
+    api.server_users().user(name).get().and_then(User::try_from)
+}
+
+/// Deletes a user. Requires server administrator privileges.
+pub fn delete_user(api: &ApiClient, name: &str) -> Result<(), Error> {
+    api.server_users().user(name).delete().map(|_| ())
+}
+
+/// Lists the names of the users on the server, optionally only those with the given email
+/// address.
+pub fn list_users(api: &ApiClient, email: Option<&str>) -> Result<Vec<String>, Error> {
+    let mut req = api.server_users();
+    if let Some(email) = email {
+        req.email(email);
+    }
+    let users: UserList = req.get()?.into();
+    Ok(users.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn barney() -> User {
+        User {
+            username: String::from("barney"),
+            display_name: String::from("Barney Rubble"),
+            email: String::from("barney@bedrock.com"),
+            first_name: String::from("Barney"),
+            last_name: String::from("Rubble"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_user_round_trip() {
+        let json = serde_json::to_value(barney()).unwrap();
+        assert!(json.get("middle_name").is_none());
+        assert!(json.get("public_key").is_none());
+        let user = User::try_from(json.clone()).unwrap();
+        assert_eq!(user.email, "barney@bedrock.com");
+        assert_eq!(serde_json::to_value(&user).unwrap(), json);
+    }
+
+    #[test]
+    fn test_user_create_request() {
+        let user = barney();
+        let body = UserCreateRequest {
+            user: &user,
+            password: Some("yabbadabbadoo"),
+            create_key: Some(true),
+        };
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["username"], "barney");
+        assert_eq!(json["password"], "yabbadabbadoo");
+        assert_eq!(json["create_key"], true);
+    }
+
+    #[test]
+    fn test_created_user() {
+        let uri = "https://chef.example/users/barney";
+        let resp = json!({"uri": uri, "chef_key": {"private_key": "v1 key"}});
+        let created = created_user(String::from("barney"), &resp, None);
+        assert_eq!(created.uri.as_deref(), Some(uri));
+        assert_eq!(created.private_key.as_deref(), Some("v1 key"));
+
+        let resp = json!({"uri": uri, "private_key": "v0 key"});
+        let created = created_user(String::from("barney"), &resp, None);
+        assert_eq!(created.private_key.as_deref(), Some("v0 key"));
+
+        let created = created_user(String::from("barney"), &resp, Some("local".into()));
+        assert_eq!(created.private_key.as_deref(), Some("local"));
+    }
+
+    #[test]
+    fn test_user_list() {
+        let users: UserList = json!({
+            "barney": "https://chef.example/users/barney",
+            "fred": "https://chef.example/users/fred"
+        })
+        .into();
+        let mut names: Vec<String> = users.collect();
+        names.sort();
+        assert_eq!(names, vec!["barney", "fred"]);
+    }
+}
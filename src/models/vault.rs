@@ -0,0 +1,445 @@
+model_use!();
+
+use base64::{engine::general_purpose, Engine as _};
+use chef_api::api_client::{ApiClient, Execute};
+use chef_api::errors::ChefError;
+use openssl::rsa::{Padding, Rsa};
+use serde::{Deserializer, Serializer};
+
+use crate::models::{
+    decrypt_item_data, delete_data_bag_item, encrypt_item_data, get_data_bag_item, get_key,
+    is_status, save_data_bag_item, DataBagItem, DataBagSecret, EncryptionVersion, KeyOwner,
+    PartialResultResult,
+};
+
+const SECRET_LENGTH: usize = 32;
+
+/// The `_keys` item of a chef-vault item. It lists who may read the vault item, and holds the
+/// vault's shared secret encrypted with each of their public keys.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct VaultKeys {
+    pub id: String,
+    pub admins: Vec<String>,
+    pub clients: Vec<String>,
+    #[serde(
+        deserialize_with = "deserialize_search_query",
+        serialize_with = "serialize_search_query"
+    )]
+    pub search_query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// The encrypted shared secret for each admin and client, keyed by name.
+    #[serde(flatten)]
+    pub keys: HashMap<String, Value>,
+}
+
+model_impl!(VaultKeys);
+
+// chef-vault stores an empty search query as an empty list.
+fn deserialize_search_query<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let query: Value = serde::Deserialize::deserialize(deserializer)?;
+    Ok(query.as_str().filter(|q| !q.is_empty()).map(String::from))
+}
+
+fn serialize_search_query<S>(query: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match *query {
+        Some(ref query) => serializer.serialize_str(query),
+        None => serializer.collect_seq(Vec::<String>::new()),
+    }
+}
+
+/// Who may read a vault item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VaultAccess {
+    /// Users (or clients) that administer the vault item.
+    pub admins: Vec<String>,
+    /// Clients that may read the vault item, in addition to those matching `search_query`.
+    pub clients: Vec<String>,
+    /// A node search query; the clients of all matching nodes may read the vault item.
+    pub search_query: Option<String>,
+}
+
+fn keys_id(item: &str) -> String {
+    format!("{}_keys", item)
+}
+
+/// The id of the item holding `actor`'s encrypted secret in sparse mode, which chef-vault forms
+/// from the id of the `_keys` item, e.g. `mysql_keys_key_web01`.
+fn sparse_id(keys: &VaultKeys, actor: &str) -> String {
+    format!("{}_key_{}", keys.id, actor)
+}
+
+impl VaultKeys {
+    fn is_sparse(&self) -> bool {
+        self.mode.as_deref() == Some("sparse")
+    }
+}
+
+fn get_item_data(api: &ApiClient, vault: &str, id: &str) -> Result<HashMap<String, Value>, Error> {
+    get_data_bag_item(api, vault, id).map(|item| item.raw_data)
+}
+
+fn save_item_data(api: &ApiClient, vault: &str, data: HashMap<String, Value>) -> Result<(), Error> {
+    let id = data
+        .get("id")
+        .and_then(Value::as_str)
+        .map(String::from)
+        .unwrap_or_default();
+    save_data_bag_item(api, &DataBagItem::new(vault, &id, data)?)
+}
+
+/// Fetches the `_keys` item of a vault item.
+pub fn get_vault_keys(api: &ApiClient, vault: &str, item: &str) -> Result<VaultKeys, Error> {
+    api.data()
+        .data_bag(vault)
+        .item(&keys_id(item))
+        .get()
+        .and_then(VaultKeys::try_from)
+}
+
+fn decrypt_secret(api: &ApiClient, vault: &str, keys: &VaultKeys) -> Result<DataBagSecret, Error> {
+    let actor = api.config.client_name()?;
+    let encrypted = if keys.is_sparse() {
+        let sparse = get_item_data(api, vault, &sparse_id(keys, actor))?;
+        sparse.get(actor).cloned()
+    } else {
+        keys.keys.get(actor).cloned()
+    };
+    let encrypted = encrypted
+        .as_ref()
+        .and_then(Value::as_str)
+        .ok_or_else(|| ChefError::KeyMissingError(format!("vault secret for {}", actor)))?;
+
+    let encrypted: String = encrypted.chars().filter(|c| !c.is_whitespace()).collect();
+    let encrypted = general_purpose::STANDARD.decode(encrypted)?;
+    let secret = api.request_signer()?.decrypt(&encrypted)?;
+    Ok(DataBagSecret::new(secret))
+}
+
+fn encrypt_secret(public_key: &str, secret: &DataBagSecret) -> Result<Value, Error> {
+    let key = Rsa::public_key_from_pem(public_key.as_bytes())
+        .or_else(|_| Rsa::public_key_from_pem_pkcs1(public_key.as_bytes()))?;
+    let mut encrypted = vec![0; key.size() as usize];
+    let len = key.public_encrypt(secret.as_bytes(), &mut encrypted, Padding::PKCS1)?;
+    encrypted.truncate(len);
+    Ok(Value::String(general_purpose::STANDARD.encode(encrypted)))
+}
+
+fn public_key(api: &ApiClient, actor: KeyOwner) -> Result<String, Error> {
+    get_key(api, actor, "default")?
+        .public_key
+        .ok_or_else(|| ChefError::KeyMissingError(String::from("public_key")).into())
+}
+
+fn admin_public_key(api: &ApiClient, admin: &str) -> Result<String, Error> {
+    public_key(api, KeyOwner::User(admin)).or_else(|_| public_key(api, KeyOwner::Client(admin)))
+}
+
+fn search_clients(api: &ApiClient, query: &str) -> Result<Vec<String>, Error> {
+    let mut fields = HashMap::new();
+    fields.insert("name", vec!["name"]);
+    let rows: PartialResultResult = api
+        .search()
+        .search_index("node")
+        .q(query)
+        .post(&fields)?
+        .into();
+    Ok(rows
+        .filter_map(|r| r.data.get("name").and_then(Value::as_str).map(String::from))
+        .collect())
+}
+
+fn write_vault(
+    api: &ApiClient,
+    vault: &str,
+    item: &str,
+    values: &HashMap<String, Value>,
+    access: &VaultAccess,
+    secret: &DataBagSecret,
+) -> Result<(), Error> {
+    let mut clients = access.clients.clone();
+    if let Some(ref query) = access.search_query {
+        clients.extend(search_clients(api, query)?);
+    }
+    clients.sort();
+    clients.dedup();
+
+    let mut keys = VaultKeys {
+        id: keys_id(item),
+        admins: access.admins.clone(),
+        clients: clients.clone(),
+        search_query: access.search_query.clone(),
+        mode: Some(String::from("default")),
+        keys: HashMap::new(),
+    };
+    for admin in &access.admins {
+        let key = admin_public_key(api, admin)?;
+        keys.keys
+            .insert(admin.clone(), encrypt_secret(&key, secret)?);
+    }
+    for client in &clients {
+        let key = public_key(api, KeyOwner::Client(client))?;
+        keys.keys
+            .insert(client.clone(), encrypt_secret(&key, secret)?);
+    }
+
+    let mut values = values.clone();
+    values.insert(String::from("id"), Value::String(item.into()));
+    let values = encrypt_item_data(&values, secret, EncryptionVersion::default())?;
+    let keys: HashMap<String, Value> = serde_json::from_value(serde_json::to_value(&keys)?)?;
+
+    // Everything that can fail has been done; save the keys first, so the values are never
+    // encrypted with a secret nobody holds. If the values can't be saved, put back the
+    // previous keys, which still match the previous values.
+    let previous = get_vault_keys(api, vault, item).ok();
+    save_item_data(api, vault, keys)?;
+    if let Err(e) = save_item_data(api, vault, values) {
+        if let Some(previous) = previous {
+            let restored = serde_json::to_value(&previous)
+                .and_then(serde_json::from_value)
+                .map_err(Error::from)
+                .and_then(|previous| save_item_data(api, vault, previous));
+            if let Err(restore) = restored {
+                log::warn!(
+                    "Unable to restore the keys of vault item {}: {}",
+                    item,
+                    restore
+                );
+            }
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Reads and decrypts a vault item, using the client name and signer of `api`. A custom
+/// `Signer` must support `decrypt` to read vault items.
+///
+/// ```rust,no_run
+/// # use chef_api::api_client::ApiClient;
+/// # use chef::models::*;
+/// # let api = ApiClient::from_credentials(None).unwrap();
+/// let passwords = load_vault_item(&api, "passwords", "mysql").unwrap();
+/// println!("{:?}", passwords.get("root"));
+/// ```
+pub fn load_vault_item(
+    api: &ApiClient,
+    vault: &str,
+    item: &str,
+) -> Result<HashMap<String, Value>, Error> {
+    let keys = get_vault_keys(api, vault, item)?;
+    let secret = decrypt_secret(api, vault, &keys)?;
+    let data = get_item_data(api, vault, item)?;
+    decrypt_item_data(&data, &secret)
+}
+
+/// Creates or replaces a vault item with a freshly generated shared secret, readable by the
+/// given admins and clients.
+///
+/// Keys are always written in chef-vault's default mode.
+pub fn save_vault_item(
+    api: &ApiClient,
+    vault: &str,
+    item: &str,
+    values: &HashMap<String, Value>,
+    access: &VaultAccess,
+) -> Result<(), Error> {
+    let secret = DataBagSecret::generate(SECRET_LENGTH)?;
+    write_vault(api, vault, item, values, access, &secret)
+}
+
+/// Re-runs the search query of a vault item and shares the existing secret with any new
+/// matching clients. When `clean` is set, clients that no longer match the query lose access.
+///
+/// In sparse mode, each client's encrypted secret is kept in its own item, which is written or
+/// deleted alongside the `_keys` item.
+pub fn refresh_vault_keys(
+    api: &ApiClient,
+    vault: &str,
+    item: &str,
+    clean: bool,
+) -> Result<(), Error> {
+    let mut keys = get_vault_keys(api, vault, item)?;
+    let query = match keys.search_query {
+        Some(ref query) => query.clone(),
+        None => return Ok(()),
+    };
+    let secret = decrypt_secret(api, vault, &keys)?;
+
+    let matching = search_clients(api, &query)?;
+    let (added, removed) = refresh_changes(&keys, &matching, clean);
+    for client in &added {
+        // As chef-vault does, skip nodes that match the search but have no client yet; a later
+        // refresh picks them up.
+        let key = match public_key(api, KeyOwner::Client(client)) {
+            Err(ref e) if is_status(e, 404) => {
+                log::warn!(
+                    "Node {} matches the search query of {}/{} but has no client; skipping it",
+                    client,
+                    vault,
+                    item
+                );
+                continue;
+            }
+            key => key?,
+        };
+        let encrypted = encrypt_secret(&key, &secret)?;
+        if keys.is_sparse() {
+            let mut sparse = HashMap::new();
+            sparse.insert(String::from("id"), Value::String(sparse_id(&keys, client)));
+            sparse.insert(client.clone(), encrypted);
+            save_item_data(api, vault, sparse)?;
+        } else {
+            keys.keys.insert(client.clone(), encrypted);
+        }
+        keys.clients.push(client.clone());
+    }
+    keys.clients.retain(|c| !removed.contains(c));
+    // Admins keep their secret, even once they no longer read the item as a client.
+    let stale: Vec<String> = removed
+        .into_iter()
+        .filter(|c| !keys.admins.contains(c))
+        .collect();
+    for client in &stale {
+        keys.keys.remove(client);
+    }
+
+    let sparse = keys.is_sparse();
+    let stale: Vec<String> = stale.iter().map(|c| sparse_id(&keys, c)).collect();
+    let keys: HashMap<String, Value> = serde_json::from_value(serde_json::to_value(&keys)?)?;
+    save_item_data(api, vault, keys)?;
+    // Only drop the secrets once the `_keys` item no longer lists their clients.
+    if sparse {
+        for id in &stale {
+            delete_data_bag_item(api, vault, id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Works out which clients a refresh adds, because they match the search query but can't read
+/// the item yet, and which it removes, because they no longer match and `clean` is set.
+fn refresh_changes(
+    keys: &VaultKeys,
+    matching: &[String],
+    clean: bool,
+) -> (Vec<String>, Vec<String>) {
+    let added = matching
+        .iter()
+        .filter(|c| !keys.clients.contains(c))
+        .cloned()
+        .collect();
+    let removed = if clean {
+        keys.clients
+            .iter()
+            .filter(|c| !matching.contains(c))
+            .cloned()
+            .collect()
+    } else {
+        Vec::new()
+    };
+    (added, removed)
+}
+
+/// Re-encrypts a vault item with a new shared secret, and shares it with the current admins and
+/// the clients matching its search query.
+pub fn rotate_vault_keys(api: &ApiClient, vault: &str, item: &str) -> Result<(), Error> {
+    let keys = get_vault_keys(api, vault, item)?;
+    let secret = decrypt_secret(api, vault, &keys)?;
+    let values = decrypt_item_data(&get_item_data(api, vault, item)?, &secret)?;
+
+    let access = VaultAccess {
+        admins: keys.admins.clone(),
+        clients: keys.clients.clone(),
+        search_query: keys.search_query.clone(),
+    };
+    save_vault_item(api, vault, item, &values, &access)?;
+
+    // The item is now in default mode, so any sparse items hold the old secret.
+    if keys.is_sparse() {
+        for actor in keys.admins.iter().chain(keys.clients.iter()) {
+            if let Err(e) = delete_data_bag_item(api, vault, &sparse_id(&keys, actor)) {
+                log::warn!("Unable to delete the old sparse key of {}: {}", actor, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chef_api::authentication::signer::{PemSigner, Signer};
+    use openssl::pkey::PKey;
+
+    #[test]
+    fn test_vault_keys() {
+        let keys: VaultKeys = serde_json::from_str(
+            r#"{
+                "id": "mysql_keys",
+                "admins": ["barney"],
+                "clients": ["web01"],
+                "search_query": [],
+                "mode": "default",
+                "barney": "c2VjcmV0\n",
+                "web01": "c2VjcmV0\n"
+            }"#,
+        )
+        .unwrap();
+        assert!(keys.search_query.is_none());
+        assert_eq!(keys.keys.len(), 2);
+        assert!(keys.keys.contains_key("web01"));
+
+        let json = serde_json::to_value(&keys).unwrap();
+        assert_eq!(json["search_query"], serde_json::json!([]));
+        assert_eq!(json["barney"], serde_json::json!("c2VjcmV0\n"));
+    }
+
+    #[test]
+    fn test_refresh_changes() {
+        let keys = VaultKeys {
+            id: String::from("mysql_keys"),
+            admins: vec![String::from("barney")],
+            clients: vec![
+                String::from("web01"),
+                String::from("web02"),
+                String::from("barney"),
+            ],
+            mode: Some(String::from("sparse")),
+            ..Default::default()
+        };
+        let matching = vec![String::from("web02"), String::from("web03")];
+
+        let (added, removed) = refresh_changes(&keys, &matching, false);
+        assert_eq!(added, vec!["web03"]);
+        assert!(removed.is_empty());
+
+        let (added, removed) = refresh_changes(&keys, &matching, true);
+        assert_eq!(added, vec!["web03"]);
+        assert_eq!(removed, vec!["web01", "barney"]);
+
+        assert!(keys.is_sparse());
+        assert_eq!(sparse_id(&keys, "web03"), "mysql_keys_key_web03");
+    }
+
+    #[test]
+    fn test_secret_round_trip() {
+        let key = Rsa::generate(2048).unwrap();
+        let public = String::from_utf8(key.public_key_to_pem().unwrap()).unwrap();
+        let secret = DataBagSecret::generate(SECRET_LENGTH).unwrap();
+
+        let encrypted = encrypt_secret(&public, &secret).unwrap();
+        let encrypted = general_purpose::STANDARD
+            .decode(encrypted.as_str().unwrap())
+            .unwrap();
+        let signer = PemSigner::new(PKey::from_rsa(key).unwrap());
+        assert_eq!(signer.decrypt(&encrypted).unwrap(), secret.as_bytes());
+    }
+}
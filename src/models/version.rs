@@ -0,0 +1,274 @@
+use chef_api::errors::ChefError;
+use failure::Error;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A cookbook version, following Chef's `major.minor.patch` scheme. A missing patch level is
+/// treated as `0`, so `2.1` and `2.1.0` are equal.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+#[serde(try_from = "String", into = "String")]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses a version, also returning whether it had a patch level.
+    fn parse(version: &str) -> Result<(Self, bool), Error> {
+        let invalid = || ChefError::InvalidVersionError(version.into());
+        let parts = version
+            .trim()
+            .split('.')
+            .map(|p| {
+                if !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()) {
+                    p.parse::<u64>().map_err(|_| invalid())
+                } else {
+                    Err(invalid())
+                }
+            })
+            .collect::<Result<Vec<u64>, ChefError>>()?;
+        match parts[..] {
+            [major, minor] => Ok((Version::new(major, minor, 0), false)),
+            [major, minor, patch] => Ok((Version::new(major, minor, patch), true)),
+            _ => Err(invalid().into()),
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(version: &str) -> Result<Self, Error> {
+        Version::parse(version).map(|(v, _)| v)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl TryFrom<String> for Version {
+    type Error = Error;
+
+    fn try_from(version: String) -> Result<Self, Error> {
+        version.parse()
+    }
+}
+
+impl From<Version> for String {
+    fn from(version: Version) -> String {
+        version.to_string()
+    }
+}
+
+/// The comparison operator of a `VersionConstraint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstraintOp {
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// The pessimistic operator, `~>`: `~> 2.1` allows `>= 2.1.0, < 3.0.0`, while `~> 2.1.3`
+    /// allows `>= 2.1.3, < 2.2.0`.
+    Pessimistic,
+}
+
+impl ConstraintOp {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ConstraintOp::Eq => "=",
+            ConstraintOp::Gt => ">",
+            ConstraintOp::Gte => ">=",
+            ConstraintOp::Lt => "<",
+            ConstraintOp::Lte => "<=",
+            ConstraintOp::Pessimistic => "~>",
+        }
+    }
+}
+
+/// A constraint on a cookbook version, as used in environment `cookbook_versions` and cookbook
+/// metadata dependencies, e.g. `~> 2.1` or `>= 1.0.0`. A bare version means `=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VersionConstraint {
+    pub op: ConstraintOp,
+    pub version: Version,
+    // Whether a patch level was given, which changes the upper bound of `~>`.
+    precise: bool,
+}
+
+impl VersionConstraint {
+    pub fn new(op: ConstraintOp, version: Version) -> Self {
+        Self {
+            op,
+            version,
+            precise: true,
+        }
+    }
+
+    /// Returns true if `version` satisfies this constraint.
+    pub fn satisfies(&self, version: &Version) -> bool {
+        match self.op {
+            ConstraintOp::Eq => *version == self.version,
+            ConstraintOp::Gt => *version > self.version,
+            ConstraintOp::Gte => *version >= self.version,
+            ConstraintOp::Lt => *version < self.version,
+            ConstraintOp::Lte => *version <= self.version,
+            ConstraintOp::Pessimistic => {
+                let upper = if self.precise {
+                    Version::new(self.version.major, self.version.minor + 1, 0)
+                } else {
+                    Version::new(self.version.major + 1, 0, 0)
+                };
+                *version >= self.version && *version < upper
+            }
+        }
+    }
+
+    /// Returns the highest of `versions` that satisfies this constraint.
+    pub fn best_match<'a, I>(&self, versions: I) -> Option<&'a Version>
+    where
+        I: IntoIterator<Item = &'a Version>,
+    {
+        versions.into_iter().filter(|v| self.satisfies(v)).max()
+    }
+}
+
+impl Default for VersionConstraint {
+    /// The constraint that allows any version, `>= 0.0.0`.
+    fn default() -> Self {
+        VersionConstraint::new(ConstraintOp::Gte, Version::default())
+    }
+}
+
+impl FromStr for VersionConstraint {
+    type Err = Error;
+
+    fn from_str(constraint: &str) -> Result<Self, Error> {
+        let trimmed = constraint.trim();
+        let split = trimmed
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(trimmed.len());
+        let op = match trimmed[..split].trim() {
+            "" | "=" => ConstraintOp::Eq,
+            ">" => ConstraintOp::Gt,
+            ">=" => ConstraintOp::Gte,
+            "<" => ConstraintOp::Lt,
+            "<=" => ConstraintOp::Lte,
+            "~>" => ConstraintOp::Pessimistic,
+            _ => return Err(ChefError::InvalidVersionConstraintError(constraint.into()).into()),
+        };
+        let (version, precise) = Version::parse(&trimmed[split..])
+            .map_err(|_| ChefError::InvalidVersionConstraintError(constraint.into()))?;
+        Ok(Self {
+            op,
+            version,
+            precise,
+        })
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.precise {
+            write!(f, "{} {}", self.op.as_str(), self.version)
+        } else {
+            write!(
+                f,
+                "{} {}.{}",
+                self.op.as_str(),
+                self.version.major,
+                self.version.minor
+            )
+        }
+    }
+}
+
+impl TryFrom<String> for VersionConstraint {
+    type Error = Error;
+
+    fn try_from(constraint: String) -> Result<Self, Error> {
+        constraint.parse()
+    }
+}
+
+impl From<VersionConstraint> for String {
+    fn from(constraint: VersionConstraint) -> String {
+        constraint.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        assert_eq!(v("2.1"), v("2.1.0"));
+        assert!(v("2.10.0") > v("2.9.9"));
+        assert!(v("10.0.0") > v("9.99.99"));
+        assert_eq!(v("1.2").to_string(), "1.2.0");
+        assert!("1".parse::<Version>().is_err());
+        assert!("1.2.3.4".parse::<Version>().is_err());
+        assert!("1.a.3".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn test_constraints() {
+        let c: VersionConstraint = "~> 2.1".parse().unwrap();
+        assert!(c.satisfies(&v("2.1.0")));
+        assert!(c.satisfies(&v("2.9.3")));
+        assert!(!c.satisfies(&v("3.0.0")));
+        assert_eq!(c.to_string(), "~> 2.1");
+
+        let c: VersionConstraint = "~> 2.1.3".parse().unwrap();
+        assert!(c.satisfies(&v("2.1.9")));
+        assert!(!c.satisfies(&v("2.2.0")));
+        assert!(!c.satisfies(&v("2.1.2")));
+
+        assert!("1.0.0"
+            .parse::<VersionConstraint>()
+            .unwrap()
+            .satisfies(&v("1.0")));
+        assert!(">=1.0"
+            .parse::<VersionConstraint>()
+            .unwrap()
+            .satisfies(&v("1.5")));
+        assert!("< 1.0"
+            .parse::<VersionConstraint>()
+            .unwrap()
+            .satisfies(&v("0.9")));
+        assert!(!"> 1.0"
+            .parse::<VersionConstraint>()
+            .unwrap()
+            .satisfies(&v("1.0")));
+        assert!("<= 1.0"
+            .parse::<VersionConstraint>()
+            .unwrap()
+            .satisfies(&v("1.0")));
+        assert!("=> 1.0".parse::<VersionConstraint>().is_err());
+
+        let versions = vec![v("1.0.0"), v("2.1.4"), v("2.3.0"), v("3.0.0")];
+        let c: VersionConstraint = "~> 2.1".parse().unwrap();
+        assert_eq!(c.best_match(&versions), Some(&v("2.3.0")));
+    }
+}